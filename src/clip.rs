@@ -0,0 +1,382 @@
+use geo::*;
+
+use super::{tiles, tile_fraction_to_lon, tile_fraction_to_lat, Error};
+
+/**
+ * A tile and the part of a geometry falling within it
+ */
+pub type ClippedTile = ((i32, i32, u8), Geometry<f64>);
+
+/**
+ * Clip a geometry to each tile of its cover
+ *
+ * The clip box of each tile is grown by `buffer` units of a tile with the
+ * given extent, e.g. pixels with 256 or 512 for raster tiles or 4096 for
+ * Mapbox Vector Tiles. Tiles left with nothing after clipping, such as a tile
+ * lying entirely within a polygon's hole, are dropped
+ */
+pub fn clip_to_tiles(geom: &Geometry<f64>, zoom: u8, buffer: f64, extent: u32) -> Result<Vec<ClippedTile>, Error> {
+    let mut clipped: Vec<ClippedTile> = Vec::new();
+
+    for tile in tiles(geom, zoom)? {
+        if let Some(part) = clip_geom(geom, buffered_bbox(tile, buffer, extent)) {
+            clipped.push((tile, part));
+        }
+    }
+
+    Ok(clipped)
+}
+
+/**
+ * Get the BBOX of a tile grown by a buffer in units of the tile's extent
+ *
+ * Returned in the format [ West, South, East, North ]
+ */
+fn buffered_bbox(tile: (i32, i32, u8), buffer: f64, extent: u32) -> (f64, f64, f64, f64) {
+    let pad = buffer / extent as f64;
+
+    (
+        tile_fraction_to_lon(tile.0 as f64 - pad, tile.2),
+        tile_fraction_to_lat(tile.1 as f64 + 1.0 + pad, tile.2),
+        tile_fraction_to_lon(tile.0 as f64 + 1.0 + pad, tile.2),
+        tile_fraction_to_lat(tile.1 as f64 - pad, tile.2)
+    )
+}
+
+fn clip_geom(geom: &Geometry<f64>, bbox: (f64, f64, f64, f64)) -> Option<Geometry<f64>> {
+    match *geom {
+        Geometry::Point(ref point) => {
+            if inside(point.0, bbox) {
+                Some(Geometry::Point(*point))
+            } else {
+                None
+            }
+        },
+        Geometry::MultiPoint(ref points) => {
+            let points: Vec<Point<f64>> = points.0.iter().filter(|point| inside(point.0, bbox)).cloned().collect();
+
+            if points.is_empty() {
+                None
+            } else {
+                Some(Geometry::MultiPoint(MultiPoint(points)))
+            }
+        },
+        Geometry::LineString(ref linestring) => {
+            let mut parts = clip_line(linestring, bbox);
+
+            match parts.len() {
+                0 => None,
+                1 => parts.pop().map(Geometry::LineString),
+                _ => Some(Geometry::MultiLineString(MultiLineString(parts)))
+            }
+        },
+        Geometry::MultiLineString(ref linestrings) => {
+            let parts: Vec<LineString<f64>> = linestrings.0.iter().flat_map(|linestring| clip_line(linestring, bbox)).collect();
+
+            if parts.is_empty() {
+                None
+            } else {
+                Some(Geometry::MultiLineString(MultiLineString(parts)))
+            }
+        },
+        Geometry::Polygon(ref polygon) => {
+            clip_polygon(polygon, bbox).map(Geometry::Polygon)
+        },
+        Geometry::MultiPolygon(ref polygons) => {
+            let parts: Vec<Polygon<f64>> = polygons.0.iter().filter_map(|polygon| clip_polygon(polygon, bbox)).collect();
+
+            if parts.is_empty() {
+                None
+            } else {
+                Some(Geometry::MultiPolygon(MultiPolygon(parts)))
+            }
+        },
+        _ => None
+    }
+}
+
+fn inside(coord: Coordinate<f64>, bbox: (f64, f64, f64, f64)) -> bool {
+    coord.x >= bbox.0 && coord.y >= bbox.1 && coord.x <= bbox.2 && coord.y <= bbox.3
+}
+
+/**
+ * Clip a line to a BBOX, splitting it each time it leaves the box
+ */
+fn clip_line(linestring: &LineString<f64>, bbox: (f64, f64, f64, f64)) -> Vec<LineString<f64>> {
+    let mut parts: Vec<LineString<f64>> = Vec::new();
+    let mut current: Vec<Coordinate<f64>> = Vec::new();
+
+    for segment in linestring.0.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+
+        if a == b {
+            continue;
+        }
+
+        match clip_segment(a, b, bbox) {
+            Some((start, end)) => {
+                if current.is_empty() {
+                    current.push(start);
+                }
+                current.push(end);
+
+                // the segment left the box, so the part ends here
+                if end != b {
+                    parts.push(LineString(current.split_off(0)));
+                }
+            },
+            None => {
+                if !current.is_empty() {
+                    parts.push(LineString(current.split_off(0)));
+                }
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        parts.push(LineString(current));
+    }
+
+    parts.retain(|part| part.0.len() > 1);
+
+    parts
+}
+
+/**
+ * Liang-Barsky clip of the segment a-b to a BBOX
+ */
+fn clip_segment(a: Coordinate<f64>, b: Coordinate<f64>, bbox: (f64, f64, f64, f64)) -> Option<(Coordinate<f64>, Coordinate<f64>)> {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+
+    let mut t0: f64 = 0.0;
+    let mut t1: f64 = 1.0;
+
+    for &(p, q) in &[(-dx, a.x - bbox.0), (dx, bbox.2 - a.x), (-dy, a.y - bbox.1), (dy, bbox.3 - a.y)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let r = q / p;
+
+            if p < 0.0 {
+                if r > t1 {
+                    return None;
+                } else if r > t0 {
+                    t0 = r;
+                }
+            } else if r < t0 {
+                return None;
+            } else if r < t1 {
+                t1 = r;
+            }
+        }
+    }
+
+    let at = |t: f64| {
+        if t == 0.0 {
+            a
+        } else if t == 1.0 {
+            b
+        } else {
+            Coordinate { x: a.x + t * dx, y: a.y + t * dy }
+        }
+    };
+
+    Some((at(t0), at(t1)))
+}
+
+/**
+ * Sutherland-Hodgman clip of each ring of a polygon to a BBOX
+ *
+ * Holes that no longer enclose an area are dropped, and the polygon is
+ * dropped entirely if its holes cover all of the clipped exterior
+ */
+fn clip_polygon(polygon: &Polygon<f64>, bbox: (f64, f64, f64, f64)) -> Option<Polygon<f64>> {
+    let exterior = clip_ring(polygon.exterior(), bbox)?;
+    let exterior_area = ring_area(&exterior);
+
+    let interiors: Vec<LineString<f64>> = polygon.interiors().iter().filter_map(|interior| clip_ring(interior, bbox)).collect();
+    let interior_area: f64 = interiors.iter().map(ring_area).sum();
+
+    if exterior_area - interior_area <= exterior_area * 1e-9 {
+        return None;
+    }
+
+    Some(Polygon::new(exterior, interiors))
+}
+
+fn clip_ring(ring: &LineString<f64>, bbox: (f64, f64, f64, f64)) -> Option<LineString<f64>> {
+    let mut coords: Vec<Coordinate<f64>> = ring.0.clone();
+
+    // work on the open ring
+    if coords.len() > 1 && coords.first() == coords.last() {
+        coords.pop();
+    }
+
+    for edge in 0..4 {
+        if coords.is_empty() {
+            break;
+        }
+
+        coords = clip_ring_edge(&coords, edge, bbox);
+    }
+
+    if coords.len() < 3 {
+        return None;
+    }
+
+    coords.push(coords[0]);
+
+    let ring = LineString(coords);
+
+    if ring_area(&ring) == 0.0 {
+        None
+    } else {
+        Some(ring)
+    }
+}
+
+/**
+ * Clip an open ring against one BBOX edge - 0: West, 1: South, 2: East, 3: North
+ */
+fn clip_ring_edge(coords: &[Coordinate<f64>], edge: u8, bbox: (f64, f64, f64, f64)) -> Vec<Coordinate<f64>> {
+    let inside = |c: &Coordinate<f64>| match edge {
+        0 => c.x >= bbox.0,
+        1 => c.y >= bbox.1,
+        2 => c.x <= bbox.2,
+        _ => c.y <= bbox.3
+    };
+
+    let intersect = |a: &Coordinate<f64>, b: &Coordinate<f64>| match edge {
+        0 => Coordinate { x: bbox.0, y: a.y + (bbox.0 - a.x) * (b.y - a.y) / (b.x - a.x) },
+        1 => Coordinate { x: a.x + (bbox.1 - a.y) * (b.x - a.x) / (b.y - a.y), y: bbox.1 },
+        2 => Coordinate { x: bbox.2, y: a.y + (bbox.2 - a.x) * (b.y - a.y) / (b.x - a.x) },
+        _ => Coordinate { x: a.x + (bbox.3 - a.y) * (b.x - a.x) / (b.y - a.y), y: bbox.3 }
+    };
+
+    let mut clipped: Vec<Coordinate<f64>> = Vec::new();
+    let mut prev = &coords[coords.len() - 1];
+
+    for current in coords {
+        if inside(current) {
+            if !inside(prev) {
+                clipped.push(intersect(prev, current));
+            }
+            clipped.push(*current);
+        } else if inside(prev) {
+            clipped.push(intersect(prev, current));
+        }
+
+        prev = current;
+    }
+
+    clipped
+}
+
+// Unsigned shoelace area of a closed ring
+fn ring_area(ring: &LineString<f64>) -> f64 {
+    let sum: f64 = ring.0.windows(2).map(|w| w[0].x * w[1].y - w[1].x * w[0].y).sum();
+
+    (sum / 2.0).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clip_line() {
+        let line: Geometry<f64> = LineString(vec![
+            Coordinate { x: -10.0, y: 0.5 },
+            Coordinate { x: 10.0, y: 0.5 }
+        ]).into();
+
+        assert_eq!(clip_to_tiles(&line, 1, 0.0, 256).unwrap(), vec![
+            ((0, 0, 1), LineString(vec![
+                Coordinate { x: -10.0, y: 0.5 },
+                Coordinate { x: 0.0, y: 0.5 }
+            ]).into()),
+            ((1, 0, 1), LineString(vec![
+                Coordinate { x: 0.0, y: 0.5 },
+                Coordinate { x: 10.0, y: 0.5 }
+            ]).into())
+        ]);
+
+        let buffered = clip_to_tiles(&line, 1, 25.6, 256).unwrap();
+        assert_eq!(buffered[0].1, line);
+        assert_eq!(buffered[1].1, line);
+
+        // the same buffer in MVT extent units is 16 times narrower
+        let buffered = clip_to_tiles(&line, 1, 25.6, 4096).unwrap();
+        assert_eq!(buffered, clip_to_tiles(&line, 1, 1.6, 256).unwrap());
+        assert!(buffered[0].1 != line);
+    }
+
+    #[test]
+    fn test_clip_line_reentering() {
+        let line = LineString(vec![
+            Coordinate { x: -10.0, y: 10.0 },
+            Coordinate { x: 10.0, y: 10.0 },
+            Coordinate { x: 10.0, y: 20.0 },
+            Coordinate { x: -10.0, y: 20.0 }
+        ]);
+
+        let parts = clip_line(&line, (-180.0, 0.0, 0.0, 85.0));
+        assert_eq!(parts, vec![
+            LineString(vec![Coordinate { x: -10.0, y: 10.0 }, Coordinate { x: 0.0, y: 10.0 }]),
+            LineString(vec![Coordinate { x: 0.0, y: 20.0 }, Coordinate { x: -10.0, y: 20.0 }])
+        ]);
+    }
+
+    #[test]
+    fn test_clip_polygon_donut() {
+        let poly: Geometry<f64> = Polygon::new(
+            LineString(vec![
+                Coordinate { x: -90.0, y: -60.0 },
+                Coordinate { x: 90.0, y: -60.0 },
+                Coordinate { x: 90.0, y: 60.0 },
+                Coordinate { x: -90.0, y: 60.0 },
+                Coordinate { x: -90.0, y: -60.0 }
+            ]),
+            vec![LineString(vec![
+                Coordinate { x: -80.0, y: -50.0 },
+                Coordinate { x: -80.0, y: 50.0 },
+                Coordinate { x: 80.0, y: 50.0 },
+                Coordinate { x: 80.0, y: -50.0 },
+                Coordinate { x: -80.0, y: -50.0 }
+            ])]
+        ).into();
+
+        let clipped = clip_to_tiles(&poly, 2, 0.0, 4096).unwrap();
+        let tiles: Vec<(i32, i32, u8)> = clipped.iter().map(|c| c.0).collect();
+        assert_eq!(tiles, vec![(1, 1, 2), (1, 2, 2), (2, 1, 2), (2, 2, 2)]);
+
+        match clipped[0].1 {
+            Geometry::Polygon(ref polygon) => {
+                assert_eq!(ring_area(polygon.exterior()), 90.0 * 60.0);
+                assert_eq!(polygon.interiors().len(), 1);
+                assert_eq!(ring_area(&polygon.interiors()[0]), 80.0 * 50.0);
+            },
+            _ => panic!("expected a polygon")
+        }
+
+        let hole_only = Polygon::new(
+            LineString(vec![
+                Coordinate { x: 0.0, y: 0.0 },
+                Coordinate { x: 1.0, y: 0.0 },
+                Coordinate { x: 1.0, y: 1.0 },
+                Coordinate { x: 0.0, y: 0.0 }
+            ]),
+            vec![LineString(vec![
+                Coordinate { x: -1.0, y: -1.0 },
+                Coordinate { x: 2.0, y: -1.0 },
+                Coordinate { x: 2.0, y: 2.0 },
+                Coordinate { x: -1.0, y: 2.0 },
+                Coordinate { x: -1.0, y: -1.0 }
+            ])]
+        );
+        assert_eq!(clip_polygon(&hole_only, (0.0, 0.0, 1.0, 1.0)), None);
+    }
+}
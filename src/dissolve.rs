@@ -0,0 +1,380 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use geo::{Coordinate, LineString, MultiPolygon, Polygon};
+
+use super::{is_valid, tile_fraction_to_lat, tile_fraction_to_lon};
+
+// Grid vertex at the highest zoom of the tiles, x east & y south
+type Vertex = (i64, i64);
+
+// Ring along with twice its signed area
+type AreaRing = (Vec<Vertex>, i64);
+
+/**
+ * Dissolve a set of tiles into the outline of the area they cover
+ *
+ * Tiles may be at mixed zooms and may overlap. The outline is traced along
+ * the tile grid, so it is exact: exteriors are wound counter-clockwise, holes
+ * clockwise, and tiles touching only at a corner become separate polygons.
+ * Tiles failing `is_valid` are skipped
+ */
+pub fn dissolve_tiles(tiles: &[(i32, i32, u8)]) -> MultiPolygon<f64> {
+    let tiles = outermost(tiles);
+
+    let zoom = match tiles.iter().map(|tile| tile.2).max() {
+        Some(zoom) => zoom,
+        None => return MultiPolygon(Vec::new())
+    };
+
+    let mut rings: Vec<Vec<Vertex>> = Vec::new();
+    let mut edges = boundary_edges(&tiles, zoom);
+
+    while let Some(&start) = edges.keys().next() {
+        rings.extend(trace_rings(&mut edges, start));
+    }
+
+    let (exteriors, holes): (Vec<AreaRing>, Vec<AreaRing>) = rings.into_iter()
+        .map(|ring| {
+            let area = signed_area(&ring);
+            (ring, area)
+        })
+        .partition(|&(_, area)| area > 0);
+
+    let mut interiors: Vec<Vec<Vec<Vertex>>> = vec![Vec::new(); exteriors.len()];
+
+    for (hole, _) in holes {
+        // the empty cell just to the right of the hole's first edge
+        let (a, b) = (hole[0], hole[1]);
+        let (dx, dy) = ((b.0 - a.0).signum() as f64, (b.1 - a.1).signum() as f64);
+        let point = (a.0 as f64 + 0.5 * dx - 0.5 * dy, a.1 as f64 + 0.5 * dy + 0.5 * dx);
+
+        let parent = exteriors.iter().enumerate()
+            .filter(|&(_, (exterior, _))| contains(exterior, point))
+            .min_by_key(|&(_, &(_, area))| area)
+            .map(|(i, _)| i);
+
+        if let Some(i) = parent {
+            interiors[i].push(hole);
+        }
+    }
+
+    MultiPolygon(exteriors.into_iter().zip(interiors).map(|((exterior, _), interiors)| {
+        Polygon::new(
+            to_linestring(&exterior, zoom),
+            interiors.iter().map(|hole| to_linestring(hole, zoom)).collect()
+        )
+    }).collect())
+}
+
+// Valid tiles with any tile inside another one of the set dropped
+fn outermost(tiles: &[(i32, i32, u8)]) -> Vec<(i32, i32, u8)> {
+    let mut sorted: Vec<(i32, i32, u8)> = tiles.iter().cloned().filter(|tile| is_valid(*tile)).collect();
+    sorted.sort_unstable_by_key(|tile| (tile.2, tile.0, tile.1));
+    sorted.dedup();
+
+    let mut kept: HashSet<(i32, i32, u8)> = HashSet::new();
+    let mut outermost: Vec<(i32, i32, u8)> = Vec::with_capacity(sorted.len());
+
+    for tile in sorted {
+        let covered = (0..tile.2).any(|z| {
+            let shift = tile.2 - z;
+            kept.contains(&(tile.0 >> shift, tile.1 >> shift, z))
+        });
+
+        if !covered {
+            kept.insert(tile);
+            outermost.push(tile);
+        }
+    }
+
+    outermost
+}
+
+/**
+ * Directed boundary edges of a set of disjoint tiles, keyed by start vertex
+ *
+ * Every tile adds its sides running counter-clockwise on the map. Sides on the
+ * same grid line are summed, so those shared by neighbouring tiles cancel out
+ */
+fn boundary_edges(tiles: &[(i32, i32, u8)], zoom: u8) -> BTreeMap<Vertex, Vec<Vertex>> {
+    // line -> (start, end, +1 towards increasing coordinates or -1)
+    let mut rows: HashMap<i64, Vec<(i64, i64, i8)>> = HashMap::new();
+    let mut cols: HashMap<i64, Vec<(i64, i64, i8)>> = HashMap::new();
+
+    for tile in tiles {
+        let size = 1_i64 << (zoom - tile.2);
+        let (x0, y0) = (tile.0 as i64 * size, tile.1 as i64 * size);
+        let (x1, y1) = (x0 + size, y0 + size);
+
+        // south side runs east, north side west, west side south & east side north
+        rows.entry(y1).or_default().push((x0, x1, 1));
+        rows.entry(y0).or_default().push((x0, x1, -1));
+        cols.entry(x0).or_default().push((y0, y1, 1));
+        cols.entry(x1).or_default().push((y0, y1, -1));
+    }
+
+    let mut edges: BTreeMap<Vertex, Vec<Vertex>> = BTreeMap::new();
+
+    for (y, spans) in rows {
+        for (start, end) in net_spans(spans) {
+            edges.entry((start, y)).or_default().push((end, y));
+        }
+    }
+    for (x, spans) in cols {
+        for (start, end) in net_spans(spans) {
+            edges.entry((x, start)).or_default().push((x, end));
+        }
+    }
+
+    edges
+}
+
+// Sweep the sides on one grid line, returning the maximal directed runs left uncancelled
+fn net_spans(spans: Vec<(i64, i64, i8)>) -> Vec<(i64, i64)> {
+    let mut events: Vec<(i64, i8)> = Vec::with_capacity(spans.len() * 2);
+    for (start, end, weight) in spans {
+        events.push((start, weight));
+        events.push((end, -weight));
+    }
+    events.sort_unstable();
+
+    let mut runs: Vec<(i64, i64)> = Vec::new();
+    let mut net: i8 = 0;
+    let mut run: Option<(i64, i8)> = None;
+
+    let mut i = 0;
+    while i < events.len() {
+        let at = events[i].0;
+        let previous = net;
+
+        while i < events.len() && events[i].0 == at {
+            net += events[i].1;
+            i += 1;
+        }
+
+        if net.signum() != previous.signum() {
+            if let Some((start, sign)) = run.take() {
+                runs.push(if sign > 0 { (start, at) } else { (at, start) });
+            }
+            if net != 0 {
+                run = Some((at, net.signum()));
+            }
+        }
+    }
+
+    runs
+}
+
+/**
+ * Follow boundary edges from a start vertex until the ring closes
+ *
+ * Where rings touch at a vertex the sharpest left turn is taken. A ring may
+ * still pass through a vertex twice, where a hole touches its exterior, so it
+ * is split there into simple rings
+ */
+fn trace_rings(edges: &mut BTreeMap<Vertex, Vec<Vertex>>, start: Vertex) -> Vec<Vec<Vertex>> {
+    let mut ring: Vec<Vertex> = vec![start];
+    let mut from = start;
+    let mut at = take_edge(edges, start, None).expect("start vertex has an edge");
+
+    while at != start {
+        let next = take_edge(edges, at, Some(direction(from, at))).expect("boundary edges form closed rings");
+
+        ring.push(at);
+        from = at;
+        at = next;
+    }
+
+    split_ring(ring).into_iter().map(simplify).collect()
+}
+
+// Split a ring at every vertex it visits more than once
+fn split_ring(ring: Vec<Vertex>) -> Vec<Vec<Vertex>> {
+    let mut rings: Vec<Vec<Vertex>> = Vec::new();
+    let mut path: Vec<Vertex> = Vec::with_capacity(ring.len());
+    let mut seen: HashMap<Vertex, usize> = HashMap::new();
+
+    for vertex in ring {
+        if let Some(&i) = seen.get(&vertex) {
+            let loop_ring: Vec<Vertex> = path.drain(i..).collect();
+            for v in loop_ring.iter() {
+                seen.remove(v);
+            }
+
+            rings.push(loop_ring);
+        }
+
+        seen.insert(vertex, path.len());
+        path.push(vertex);
+    }
+
+    rings.push(path);
+
+    rings
+}
+
+// Remove and return the edge leaving a vertex that turns furthest left of the heading
+fn take_edge(edges: &mut BTreeMap<Vertex, Vec<Vertex>>, at: Vertex, heading: Option<(i64, i64)>) -> Option<Vertex> {
+    let ends = edges.get_mut(&at)?;
+
+    let index = match heading {
+        Some(heading) => (0..ends.len()).max_by_key(|&i| turn(heading, direction(at, ends[i])))?,
+        None => 0
+    };
+
+    let end = ends.swap_remove(index);
+    if ends.is_empty() {
+        edges.remove(&at);
+    }
+
+    Some(end)
+}
+
+fn direction(from: Vertex, to: Vertex) -> (i64, i64) {
+    ((to.0 - from.0).signum(), (to.1 - from.1).signum())
+}
+
+// Positive for a left turn on the map, where grid y runs south
+fn turn(heading: (i64, i64), next: (i64, i64)) -> i64 {
+    if heading == (-next.0, -next.1) {
+        return -2;
+    }
+
+    heading.1 * next.0 - heading.0 * next.1
+}
+
+// Drop vertices in the middle of straight runs and start at the south west corner
+fn simplify(ring: Vec<Vertex>) -> Vec<Vertex> {
+    let len = ring.len();
+
+    let mut corners: Vec<Vertex> = (0..len)
+        .filter(|&i| direction(ring[(i + len - 1) % len], ring[i]) != direction(ring[i], ring[(i + 1) % len]))
+        .map(|i| ring[i])
+        .collect();
+
+    if let Some(start) = (0..corners.len()).min_by_key(|&i| (corners[i].0, -corners[i].1)) {
+        corners.rotate_left(start);
+    }
+
+    corners
+}
+
+// Twice the area on the map, positive for counter-clockwise rings
+fn signed_area(ring: &[Vertex]) -> i64 {
+    let len = ring.len();
+
+    (0..len).map(|i| {
+        let (a, b) = (ring[i], ring[(i + 1) % len]);
+        b.0 * a.1 - a.0 * b.1
+    }).sum()
+}
+
+fn contains(ring: &[Vertex], point: (f64, f64)) -> bool {
+    let len = ring.len();
+    let mut inside = false;
+
+    for i in 0..len {
+        let (a, b) = (ring[i], ring[(i + 1) % len]);
+        let (ax, ay, bx, by) = (a.0 as f64, a.1 as f64, b.0 as f64, b.1 as f64);
+
+        if (ay > point.1) != (by > point.1) && point.0 < ax + (point.1 - ay) / (by - ay) * (bx - ax) {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+fn to_linestring(ring: &[Vertex], zoom: u8) -> LineString<f64> {
+    let mut coords: Vec<Coordinate<f64>> = ring.iter().map(|vertex| Coordinate {
+        x: tile_fraction_to_lon(vertex.0 as f64, zoom),
+        y: tile_fraction_to_lat(vertex.1 as f64, zoom)
+    }).collect();
+
+    coords.push(coords[0]);
+
+    LineString(coords)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{descendants, tile_to_polygon, tile_to_bbox};
+
+    #[test]
+    fn test_dissolve_single() {
+        assert_eq!(dissolve_tiles(&[]), MultiPolygon(vec![]));
+        assert_eq!(dissolve_tiles(&[(5, 10, 10)]), MultiPolygon(vec![tile_to_polygon((5, 10, 10))]));
+
+        // children dissolve into their parent, as do tiles inside another
+        let children: Vec<(i32, i32, u8)> = descendants((5, 10, 10), 13).collect();
+        assert_eq!(dissolve_tiles(&children), MultiPolygon(vec![tile_to_polygon((5, 10, 10))]));
+        assert_eq!(dissolve_tiles(&[(5, 10, 10), (40, 80, 13), (5, 10, 10)]), MultiPolygon(vec![tile_to_polygon((5, 10, 10))]));
+    }
+
+    #[test]
+    fn test_dissolve_holes() {
+        // 3x3 block with the center removed
+        let frame: Vec<(i32, i32, u8)> = descendants((0, 0, 0), 4)
+            .filter(|tile| tile.0 < 3 && tile.1 < 3 && *tile != (1, 1, 4))
+            .collect();
+
+        let dissolved = dissolve_tiles(&frame);
+        assert_eq!(dissolved.0.len(), 1);
+        assert_eq!(dissolved.0[0].exterior().0.len(), 5);
+        assert_eq!(dissolved.0[0].interiors().len(), 1);
+
+        let (west, south, east, north) = tile_to_bbox((1, 1, 4));
+        assert_eq!(dissolved.0[0].interiors()[0].0, vec![
+            Coordinate { x: west, y: south },
+            Coordinate { x: west, y: north },
+            Coordinate { x: east, y: north },
+            Coordinate { x: east, y: south },
+            Coordinate { x: west, y: south }
+        ]);
+
+        // an island inside a hole is a polygon of its own
+        let mut island: Vec<(i32, i32, u8)> = descendants((0, 0, 0), 4)
+            .filter(|tile| tile.0 < 5 && tile.1 < 5 && !(tile.0 >= 1 && tile.0 <= 3 && tile.1 >= 1 && tile.1 <= 3))
+            .collect();
+        island.push((2, 2, 4));
+
+        let dissolved = dissolve_tiles(&island);
+        assert_eq!(dissolved.0.len(), 2);
+        assert_eq!(dissolved.0[0].interiors().len(), 1);
+        assert_eq!(dissolved.0[1], tile_to_polygon((2, 2, 4)));
+    }
+
+    #[test]
+    fn test_dissolve_mixed() {
+        // a ring of z4 tiles around a z3 tile split into z5 tiles, leaving one z5 hole
+        let mut tiles: Vec<(i32, i32, u8)> = descendants((0, 0, 1), 4)
+            .filter(|tile| !(tile.0 >= 2 && tile.0 <= 3 && tile.1 >= 2 && tile.1 <= 3))
+            .collect();
+        tiles.extend(descendants((1, 1, 3), 5).filter(|tile| *tile != (5, 5, 5)));
+
+        let dissolved = dissolve_tiles(&tiles);
+        assert_eq!(dissolved.0.len(), 1);
+        assert_eq!(dissolved.0[0], Polygon::new(tile_to_polygon((0, 0, 1)).exterior().clone(), vec![
+            LineString(tile_to_polygon((5, 5, 5)).exterior().0.iter().rev().cloned().collect::<Vec<_>>())
+        ]));
+    }
+
+    #[test]
+    fn test_dissolve_touching_corners() {
+        // a checkerboard of 4 tiles dissolves into 4 squares
+        let board = [(0, 0, 2), (1, 1, 2), (2, 0, 2), (0, 2, 2)];
+        let dissolved = dissolve_tiles(&board);
+        assert_eq!(dissolved.0.len(), 4);
+        assert!(dissolved.0.iter().all(|polygon| polygon.exterior().0.len() == 5 && polygon.interiors().is_empty()));
+
+        // a hole touching the exterior at a corner stays a hole
+        let notched: Vec<(i32, i32, u8)> = descendants((0, 0, 0), 3)
+            .filter(|tile| tile.0 < 3 && tile.1 < 3 && *tile != (1, 1, 3) && *tile != (2, 2, 3))
+            .collect();
+        let dissolved = dissolve_tiles(&notched);
+        assert_eq!(dissolved.0.len(), 1);
+        assert_eq!(dissolved.0[0].interiors().len(), 1);
+        assert_eq!(dissolved.0[0].exterior().0.len(), 7);
+    }
+}
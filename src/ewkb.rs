@@ -0,0 +1,296 @@
+use geo::*;
+
+use super::{geom_from_srid, tiles, Error};
+
+const EWKB_Z: u32 = 0x8000_0000;
+const EWKB_M: u32 = 0x4000_0000;
+const EWKB_SRID: u32 = 0x2000_0000;
+
+// Collections are rarely nested more than once; guards the stack against crafted input
+const MAX_NESTING_DEPTH: u8 = 32;
+
+/**
+ * Parse WKB or PostGIS EWKB into a lon/lat geometry
+ *
+ * An EWKB SRID of 4326 or 3857 is honoured; plain WKB is taken to be
+ * EPSG:4326. Z and M values, in either EWKB or ISO form, are dropped
+ */
+pub fn wkb_to_geom(bytes: &[u8]) -> Result<Geometry<f64>, Error> {
+    let mut reader = Reader { bytes, pos: 0 };
+
+    let (geom, srid) = reader.geometry(0)?;
+
+    if reader.pos != bytes.len() {
+        return Err(Error::WkbParse(format!("{} trailing bytes", bytes.len() - reader.pos)));
+    }
+
+    geom_from_srid(geom, srid.unwrap_or(4326))
+}
+
+/**
+ * Parse hex encoded WKB or EWKB, as output by PostGIS, into a lon/lat geometry
+ */
+pub fn hex_wkb_to_geom(text: &str) -> Result<Geometry<f64>, Error> {
+    let text = text.trim();
+
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return Err(Error::WkbParse(String::from("invalid hex")));
+    }
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(text.len() / 2);
+    for i in (0..text.len()).step_by(2) {
+        match u8::from_str_radix(&text[i..i + 2], 16) {
+            Ok(byte) => bytes.push(byte),
+            Err(_) => return Err(Error::WkbParse(String::from("invalid hex")))
+        }
+    }
+
+    wkb_to_geom(&bytes)
+}
+
+/**
+ * Get the tiles covering a WKB or EWKB geometry
+ */
+pub fn tiles_wkb(bytes: &[u8], zoom: u8) -> Result<Vec<(i32, i32, u8)>, Error> {
+    tiles(&wkb_to_geom(bytes)?, zoom)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() - self.pos < n {
+            return Err(Error::WkbParse(String::from("unexpected end of WKB")));
+        }
+
+        let taken = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+
+        Ok(taken)
+    }
+
+    fn u32(&mut self, little: bool) -> Result<u32, Error> {
+        let mut buf = [0_u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+
+        Ok(if little { u32::from_le_bytes(buf) } else { u32::from_be_bytes(buf) })
+    }
+
+    fn f64(&mut self, little: bool) -> Result<f64, Error> {
+        let mut buf = [0_u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+
+        Ok(if little { f64::from_le_bytes(buf) } else { f64::from_be_bytes(buf) })
+    }
+
+    // Read a count, refusing ones that could not possibly fit in the remaining bytes
+    fn count(&mut self, little: bool, min_size: usize) -> Result<usize, Error> {
+        let count = self.u32(little)? as usize;
+
+        if count.saturating_mul(min_size) > self.bytes.len() - self.pos {
+            return Err(Error::WkbParse(format!("count of {} exceeds the WKB length", count)));
+        }
+
+        Ok(count)
+    }
+
+    fn coords(&mut self, little: bool, dims: usize) -> Result<Vec<Coordinate<f64>>, Error> {
+        let count = self.count(little, dims * 8)?;
+
+        let mut coords: Vec<Coordinate<f64>> = Vec::with_capacity(count);
+        for _ in 0..count {
+            coords.push(self.coord(little, dims)?);
+        }
+
+        Ok(coords)
+    }
+
+    fn coord(&mut self, little: bool, dims: usize) -> Result<Coordinate<f64>, Error> {
+        let x = self.f64(little)?;
+        let y = self.f64(little)?;
+
+        // skip Z and M
+        self.take((dims - 2) * 8)?;
+
+        Ok(Coordinate { x, y })
+    }
+
+    fn geometry(&mut self, depth: u8) -> Result<(Geometry<f64>, Option<i32>), Error> {
+        if depth > MAX_NESTING_DEPTH {
+            return Err(Error::WkbParse(String::from("geometry collections nested too deeply")));
+        }
+
+        let little = match self.take(1)?[0] {
+            0 => false,
+            1 => true,
+            order => return Err(Error::WkbParse(format!("invalid byte order {}", order)))
+        };
+
+        let raw = self.u32(little)?;
+
+        let srid = if raw & EWKB_SRID != 0 {
+            Some(self.u32(little)? as i32)
+        } else {
+            None
+        };
+
+        // EWKB flags Z & M in the high bits, ISO WKB adds 1000, 2000 or 3000 to the type
+        let mut dims = 2;
+        if raw & EWKB_Z != 0 {
+            dims += 1;
+        }
+        if raw & EWKB_M != 0 {
+            dims += 1;
+        }
+
+        let iso = raw & 0x0FFF_FFFF;
+        dims += match iso / 1000 {
+            0 => 0,
+            1 | 2 => 1,
+            3 => 2,
+            _ => return Err(Error::WkbParse(format!("unknown geometry type {}", iso)))
+        };
+
+        let geom = match iso % 1000 {
+            1 => {
+                let coord = self.coord(little, dims)?;
+
+                if coord.x.is_nan() && coord.y.is_nan() {
+                    return Err(Error::WkbParse(String::from("empty points are not supported")));
+                }
+
+                Geometry::Point(Point(coord))
+            },
+            2 => Geometry::LineString(LineString(self.coords(little, dims)?)),
+            3 => Geometry::Polygon(self.polygon(little, dims)?),
+            4 => Geometry::MultiPoint(MultiPoint(self.members(little, depth, |geom| match geom {
+                Geometry::Point(point) => Some(point),
+                _ => None
+            })?)),
+            5 => Geometry::MultiLineString(MultiLineString(self.members(little, depth, |geom| match geom {
+                Geometry::LineString(linestring) => Some(linestring),
+                _ => None
+            })?)),
+            6 => Geometry::MultiPolygon(MultiPolygon(self.members(little, depth, |geom| match geom {
+                Geometry::Polygon(polygon) => Some(polygon),
+                _ => None
+            })?)),
+            7 => Geometry::GeometryCollection(GeometryCollection(self.members(little, depth, Some)?)),
+            kind => return Err(Error::WkbParse(format!("unknown geometry type {}", kind)))
+        };
+
+        Ok((geom, srid))
+    }
+
+    fn polygon(&mut self, little: bool, dims: usize) -> Result<Polygon<f64>, Error> {
+        let count = self.count(little, 4)?;
+
+        if count == 0 {
+            return Err(Error::WkbParse(String::from("empty polygons are not supported")));
+        }
+
+        let exterior = LineString(self.coords(little, dims)?);
+
+        let mut interiors: Vec<LineString<f64>> = Vec::with_capacity(count - 1);
+        for _ in 1..count {
+            interiors.push(LineString(self.coords(little, dims)?));
+        }
+
+        Ok(Polygon::new(exterior, interiors))
+    }
+
+    // Read the members of a multi geometry, each a full WKB geometry of the expected type
+    fn members<T, F>(&mut self, little: bool, depth: u8, expect: F) -> Result<Vec<T>, Error>
+        where F: Fn(Geometry<f64>) -> Option<T>
+    {
+        let count = self.count(little, 5)?;
+
+        let mut members: Vec<T> = Vec::with_capacity(count);
+        for _ in 0..count {
+            match expect(self.geometry(depth + 1)?.0) {
+                Some(member) => members.push(member),
+                None => return Err(Error::WkbParse(String::from("unexpected member geometry type")))
+            }
+        }
+
+        Ok(members)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wkb_to_geom() {
+        // POINT(1 2)
+        assert_eq!(hex_wkb_to_geom("0101000000000000000000F03F0000000000000040").unwrap(), Point::new(1.0, 2.0).into());
+        // big endian POINT(1 2)
+        assert_eq!(hex_wkb_to_geom("00000000013FF00000000000004000000000000000").unwrap(), Point::new(1.0, 2.0).into());
+        // SRID=4326;POINT(1 2)
+        assert_eq!(hex_wkb_to_geom("0101000020E6100000000000000000F03F0000000000000040").unwrap(), Point::new(1.0, 2.0).into());
+        // ISO POINT Z (1 2 3)
+        assert_eq!(hex_wkb_to_geom("01E9030000000000000000F03F00000000000000400000000000000840").unwrap(), Point::new(1.0, 2.0).into());
+        // SRID=3857;POINT(0 0)
+        assert_eq!(hex_wkb_to_geom("0101000020110F000000000000000000000000000000000000").unwrap(), Point::new(0.0, 0.0).into());
+
+        // MULTILINESTRING((0 0,1 1),(2 2,3 3))
+        assert_eq!(hex_wkb_to_geom("01050000000200000001020000000200000000000000000000000000000000000000000000000000F03F000000000000F03F0102000000020000000000000000000040000000000000004000000000000008400000000000000840").unwrap(), MultiLineString(vec![
+            LineString(vec![Coordinate { x: 0.0, y: 0.0 }, Coordinate { x: 1.0, y: 1.0 }]),
+            LineString(vec![Coordinate { x: 2.0, y: 2.0 }, Coordinate { x: 3.0, y: 3.0 }])
+        ]).into());
+    }
+
+    #[test]
+    fn test_tiles_wkb() {
+        // POLYGON((5.11962890625 20.46818922264095, ...))
+        let mut bytes: Vec<u8> = vec![1, 3, 0, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0];
+        for &(x, y) in &[
+            (5.11962890625, 20.46818922264095),
+            (5.11962890625, 20.7663868125152),
+            (5.504150390625, 20.7663868125152),
+            (5.504150390625, 20.46818922264095),
+            (5.11962890625, 20.46818922264095)
+        ] {
+            bytes.extend_from_slice(&f64::to_le_bytes(x));
+            bytes.extend_from_slice(&f64::to_le_bytes(y));
+        }
+
+        assert_eq!(tiles_wkb(&bytes, 8).unwrap(), vec![(131, 112, 8), (131, 113, 8)]);
+
+        // LINESTRING EMPTY
+        assert_eq!(tiles_wkb(&[1, 2, 0, 0, 0, 0, 0, 0, 0], 5).unwrap(), vec![]);
+        // POLYGON(EMPTY), a polygon holding an empty ring
+        assert_eq!(tiles_wkb(&[1, 3, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0], 5).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_wkb_errors() {
+        assert_eq!(hex_wkb_to_geom("0101000020E6100000000000000000F03F"), Err(Error::WkbParse(String::from("unexpected end of WKB"))));
+        assert_eq!(hex_wkb_to_geom("0101000020E6100000000000000000F03F000000000000004000"), Err(Error::WkbParse(String::from("1 trailing bytes"))));
+        assert_eq!(hex_wkb_to_geom("0201000000"), Err(Error::WkbParse(String::from("invalid byte order 2"))));
+        assert_eq!(hex_wkb_to_geom("0109000000"), Err(Error::WkbParse(String::from("unknown geometry type 9"))));
+        assert_eq!(hex_wkb_to_geom("01A10F0000"), Err(Error::WkbParse(String::from("unknown geometry type 4001"))));
+        assert_eq!(hex_wkb_to_geom("01020000000000FFFF"), Err(Error::WkbParse(String::from("count of 4294901760 exceeds the WKB length"))));
+        assert_eq!(hex_wkb_to_geom("0XZ"), Err(Error::WkbParse(String::from("invalid hex"))));
+        assert_eq!(hex_wkb_to_geom("010100002068010000000000000000F03F0000000000000040"), Err(Error::SridNotSupported(360)));
+
+        // GEOMETRYCOLLECTION(GEOMETRYCOLLECTION(...)) nested up to the limit, then deeper than the stack should go
+        let mut nested: Vec<u8> = Vec::new();
+        for _ in 0..MAX_NESTING_DEPTH {
+            nested.extend_from_slice(&[1, 7, 0, 0, 0, 1, 0, 0, 0]);
+        }
+        nested.extend_from_slice(&[1, 7, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(wkb_to_geom(&nested).is_ok());
+
+        let mut nested: Vec<u8> = Vec::new();
+        for _ in 0..200_000 {
+            nested.extend_from_slice(&[1, 7, 0, 0, 0, 1, 0, 0, 0]);
+        }
+        nested.extend_from_slice(&[1, 7, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(wkb_to_geom(&nested), Err(Error::WkbParse(String::from("geometry collections nested too deeply"))));
+    }
+}
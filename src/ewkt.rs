@@ -0,0 +1,95 @@
+use geo::Geometry;
+use wkt::Wkt;
+use wkt::conversion::try_into_geometry;
+
+use super::{geom_from_srid, tiles, Error};
+
+/**
+ * Parse WKT or PostGIS EWKT into a lon/lat geometry
+ *
+ * An EWKT `SRID=<srid>;` prefix of 4326 or 3857 is honoured; plain WKT is
+ * taken to be EPSG:4326
+ */
+pub fn wkt_to_geom(text: &str) -> Result<Geometry<f64>, Error> {
+    let (srid, text) = split_srid(text.trim())?;
+
+    let wkt: Wkt<f64> = Wkt::from_str(text).map_err(|err| Error::WktParse(String::from(err)))?;
+
+    let item = match wkt.items.first() {
+        Some(item) => item,
+        None => return Err(Error::WktParse(String::from("no geometry found")))
+    };
+
+    let geom = try_into_geometry(item).map_err(|err| Error::WktParse(err.to_string()))?;
+
+    geom_from_srid(geom, srid)
+}
+
+/**
+ * Get the tiles covering a WKT or EWKT geometry
+ */
+pub fn tiles_wkt(text: &str, zoom: u8) -> Result<Vec<(i32, i32, u8)>, Error> {
+    tiles(&wkt_to_geom(text)?, zoom)
+}
+
+// Split an optional `SRID=<srid>;` prefix from the WKT that follows it
+fn split_srid(text: &str) -> Result<(i32, &str), Error> {
+    if text.len() < 5 || !text[..5].eq_ignore_ascii_case("SRID=") {
+        return Ok((4326, text));
+    }
+
+    let end = match text.find(';') {
+        Some(end) => end,
+        None => return Err(Error::WktParse(String::from("SRID prefix is missing its ';'")))
+    };
+
+    match text[5..end].trim().parse::<i32>() {
+        Ok(srid) => Ok((srid, &text[end + 1..])),
+        Err(_) => Err(Error::WktParse(format!("invalid SRID '{}'", &text[5..end])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::Point;
+
+    #[test]
+    fn test_wkt_to_geom() {
+        assert_eq!(wkt_to_geom("POINT (-77.15664982795715 38.87419791355846)").unwrap(), Point::new(-77.15664982795715, 38.87419791355846).into());
+        assert_eq!(wkt_to_geom("srid=4326;POINT(1 2)").unwrap(), Point::new(1.0, 2.0).into());
+
+        match wkt_to_geom("SRID=3857;POINT(-8575605.398444 4707174.018280)").unwrap() {
+            Geometry::Point(point) => {
+                assert!((point.lng() - -77.035974).abs() < 1e-6);
+                assert!((point.lat() - 38.898717).abs() < 1e-6);
+            },
+            _ => panic!("expected a point")
+        }
+    }
+
+    #[test]
+    fn test_tiles_wkt() {
+        assert_eq!(tiles_wkt("POLYGON((5.11962890625 20.46818922264095, 5.11962890625 20.7663868125152, 5.504150390625 20.7663868125152, 5.504150390625 20.46818922264095, 5.11962890625 20.46818922264095))", 8).unwrap(), vec![
+            (131, 112, 8),
+            (131, 113, 8)
+        ]);
+        assert_eq!(tiles_wkt("SRID=3857;POINT(0 0)", 10).unwrap(), vec![(512, 512, 10)]);
+        assert_eq!(tiles_wkt("LINESTRING EMPTY", 5).unwrap(), vec![]);
+        assert_eq!(tiles_wkt("MULTILINESTRING(EMPTY,(0 0,1 1))", 5).unwrap(), vec![(16, 15, 5), (16, 16, 5)]);
+        assert_eq!(tiles_wkt("POLYGON EMPTY", 5).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_wkt_errors() {
+        assert_eq!(wkt_to_geom("SRID=2154;POINT(1 2)"), Err(Error::SridNotSupported(2154)));
+        assert_eq!(wkt_to_geom("SRID=abc;POINT(1 2)"), Err(Error::WktParse(String::from("invalid SRID 'abc'"))));
+        assert_eq!(wkt_to_geom("SRID=4326 POINT(1 2)"), Err(Error::WktParse(String::from("SRID prefix is missing its ';'"))));
+        assert_eq!(wkt_to_geom(""), Err(Error::WktParse(String::from("no geometry found"))));
+
+        match wkt_to_geom("POINT(1 2") {
+            Err(Error::WktParse(_)) => (),
+            other => panic!("expected a parse error, got {:?}", other)
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::f64::consts::PI;
+
+use geo::*;
+
+use super::{check_zoom, tiles, Error, D2R, MAX_ZOOM, R2D};
+
+/**
+ * Get the tiles covering a geometry whose segments are great-circle arcs
+ *
+ * Each segment is densified along its great circle before grid walking, and
+ * lines are split where they cross the antimeridian
+ */
+pub fn tiles_geodesic(geom: &Geometry<f64>, zoom: u8) -> Result<Vec<(i32, i32, u8)>, Error> {
+    check_zoom(zoom)?;
+
+    match *geom {
+        Geometry::LineString(ref linestring) => {
+            tiles(&Geometry::MultiLineString(geodesic_densify(linestring, zoom)), zoom)
+        },
+        Geometry::MultiLineString(ref linestrings) => {
+            let parts: Vec<LineString<f64>> = linestrings.0.iter().flat_map(|linestring| geodesic_densify(linestring, zoom).0).collect();
+
+            tiles(&Geometry::MultiLineString(MultiLineString(parts)), zoom)
+        },
+        Geometry::Polygon(ref polygon) => {
+            tiles(&Geometry::Polygon(densify_polygon(polygon, zoom)), zoom)
+        },
+        Geometry::MultiPolygon(ref polygons) => {
+            let polygons: Vec<Polygon<f64>> = polygons.0.iter().map(|polygon| densify_polygon(polygon, zoom)).collect();
+
+            tiles(&Geometry::MultiPolygon(MultiPolygon(polygons)), zoom)
+        },
+        _ => tiles(geom, zoom)
+    }
+}
+
+/**
+ * Densify a line along the great circles between its vertices
+ *
+ * Steps are short enough that the chord between two points strays less than
+ * about a tenth of a tile from the arc at the given zoom, with zooms above
+ * `MAX_ZOOM` densified as `MAX_ZOOM`. The line is split into separate parts
+ * where it crosses the antimeridian
+ */
+pub fn geodesic_densify(linestring: &LineString<f64>, zoom: u8) -> MultiLineString<f64> {
+    let mut parts: Vec<LineString<f64>> = Vec::new();
+    let mut current: Vec<Coordinate<f64>> = Vec::new();
+
+    for coord in densify(&linestring.0, zoom) {
+        if let Some(prev) = current.last().cloned() {
+            if (coord.x - prev.x).abs() > 180.0 {
+                // unwrap the next longitude relative to the previous one to find the crossing latitude
+                let east = coord.x < prev.x;
+                let edge = if east { 180.0 } else { -180.0 };
+                let next_x = if east { coord.x + 360.0 } else { coord.x - 360.0 };
+                let lat = prev.y + (coord.y - prev.y) * (edge - prev.x) / (next_x - prev.x);
+
+                current.push(Coordinate { x: edge, y: lat });
+                parts.push(LineString(current.split_off(0)));
+                current.push(Coordinate { x: -edge, y: lat });
+            }
+        }
+
+        current.push(coord);
+    }
+
+    if current.len() > 1 {
+        parts.push(LineString(current));
+    }
+
+    MultiLineString(parts)
+}
+
+fn densify_polygon(polygon: &Polygon<f64>, zoom: u8) -> Polygon<f64> {
+    Polygon::new(
+        LineString(densify(&polygon.exterior().0, zoom)),
+        polygon.interiors().iter().map(|interior| LineString(densify(&interior.0, zoom))).collect()
+    )
+}
+
+fn densify(coords: &[Coordinate<f64>], zoom: u8) -> Vec<Coordinate<f64>> {
+    // an arc of angle s strays ~s^2/8 from its chord; keep that under 1/10 of a tile of angle w
+    let tile_angle = 2.0 * PI / 2.0_f64.powi(zoom.min(MAX_ZOOM) as i32);
+    let max_step = (0.8 * tile_angle).sqrt().min(D2R);
+
+    let mut dense: Vec<Coordinate<f64>> = Vec::new();
+
+    for segment in coords.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+
+        dense.push(a);
+
+        let distance = angular_distance(a, b);
+
+        // antipodal points have no single great circle between them
+        if distance == 0.0 || (PI - distance).abs() < 1e-12 {
+            continue;
+        }
+
+        let steps = (distance / max_step).ceil() as usize;
+        for step in 1..steps {
+            dense.push(interpolate(a, b, distance, step as f64 / steps as f64));
+        }
+    }
+
+    if let Some(last) = coords.last() {
+        dense.push(*last);
+    }
+
+    dense
+}
+
+// Haversine angular distance in radians
+fn angular_distance(a: Coordinate<f64>, b: Coordinate<f64>) -> f64 {
+    let dlat = (b.y - a.y) * D2R;
+    let dlon = (b.x - a.x) * D2R;
+
+    let h = (dlat / 2.0).sin().powi(2) + (a.y * D2R).cos() * (b.y * D2R).cos() * (dlon / 2.0).sin().powi(2);
+
+    2.0 * h.sqrt().min(1.0).asin()
+}
+
+// Point a fraction f of the way along the great circle from a to b
+fn interpolate(a: Coordinate<f64>, b: Coordinate<f64>, distance: f64, f: f64) -> Coordinate<f64> {
+    let (lon1, lat1) = (a.x * D2R, a.y * D2R);
+    let (lon2, lat2) = (b.x * D2R, b.y * D2R);
+
+    let wa = ((1.0 - f) * distance).sin() / distance.sin();
+    let wb = (f * distance).sin() / distance.sin();
+
+    let x = wa * lat1.cos() * lon1.cos() + wb * lat2.cos() * lon2.cos();
+    let y = wa * lat1.cos() * lon1.sin() + wb * lat2.cos() * lon2.sin();
+    let z = wa * lat1.sin() + wb * lat2.sin();
+
+    Coordinate {
+        x: R2D * y.atan2(x),
+        y: R2D * z.atan2((x * x + y * y).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::point_to_tile;
+
+    #[test]
+    fn test_tiles_geodesic() {
+        let line: Geometry<f64> = LineString(vec![
+            Coordinate { x: -73.78, y: 40.64 },
+            Coordinate { x: -0.45, y: 51.47 }
+        ]).into();
+
+        let midpoint = point_to_tile(-41.30235144925488, 52.21713024842938, 5);
+
+        assert!(tiles_geodesic(&line, 5).unwrap().contains(&midpoint));
+        assert!(!tiles(&line, 5).unwrap().contains(&midpoint));
+    }
+
+    #[test]
+    fn test_geodesic_zoom_limit() {
+        let line = LineString(vec![
+            Coordinate { x: -73.78, y: 40.64 },
+            Coordinate { x: -0.45, y: 51.47 }
+        ]);
+
+        assert_eq!(tiles_geodesic(&line.clone().into(), 200), Err(Error::ZoomTooLarge(200)));
+        assert_eq!(geodesic_densify(&line, 200), geodesic_densify(&line, MAX_ZOOM));
+    }
+
+    #[test]
+    fn test_tiles_geodesic_antimeridian() {
+        let line: Geometry<f64> = LineString(vec![
+            Coordinate { x: 139.78, y: 35.55 },
+            Coordinate { x: -122.38, y: 37.62 }
+        ]).into();
+
+        let cover = tiles_geodesic(&line, 3).unwrap();
+
+        assert!(cover.contains(&(0, 2, 3)));
+        assert!(cover.contains(&(7, 2, 3)));
+        assert!(cover.iter().all(|tile| tile.0 <= 1 || tile.0 >= 6));
+    }
+
+    #[test]
+    fn test_geodesic_densify() {
+        let line = LineString(vec![
+            Coordinate { x: 170.0, y: 0.0 },
+            Coordinate { x: -170.0, y: 0.0 }
+        ]);
+
+        let parts = geodesic_densify(&line, 0);
+        assert_eq!(parts.0.len(), 2);
+        assert_eq!(parts.0[0].0.first(), Some(&Coordinate { x: 170.0, y: 0.0 }));
+        assert_eq!(parts.0[0].0.last().unwrap().x, 180.0);
+        assert_eq!(parts.0[1].0.first().unwrap().x, -180.0);
+        assert_eq!(parts.0[1].0.last(), Some(&Coordinate { x: -170.0, y: 0.0 }));
+        assert!(parts.0.iter().flat_map(|part| part.0.iter()).all(|coord| coord.y.abs() < 1e-9));
+    }
+}
@@ -1,108 +1,679 @@
 extern crate geo;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "mbtiles")]
+extern crate rusqlite;
+#[cfg(feature = "pmtiles")]
+extern crate flate2;
+#[cfg(feature = "wkt")]
+extern crate wkt;
+#[cfg(feature = "geojson")]
+extern crate geojson;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
 use std::f64::consts::PI;
-use std::f64::INFINITY as INF;
 use std::cmp::Ordering;
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::ops::AddAssign;
 use geo::*;
+use geo::prelude::MapCoords;
+
+mod clip;
+mod geodesic;
+mod url;
+mod tile_str;
+mod metrics;
+mod dissolve;
+#[cfg(feature = "mbtiles")]
+mod mbtiles;
+#[cfg(feature = "pmtiles")]
+mod pmtiles;
+#[cfg(feature = "wkt")]
+mod ewkt;
+#[cfg(feature = "wkb")]
+mod ewkb;
+#[cfg(feature = "geojson")]
+mod stream;
+#[cfg(feature = "serde")]
+pub mod tile_serde;
+
+pub use clip::{clip_to_tiles, ClippedTile};
+pub use geodesic::{tiles_geodesic, geodesic_densify};
+pub use url::{tile_url, tile_urls};
+pub use tile_str::{TileFormat, format_tile, parse_tile, parse_tile_range};
+pub use dissolve::dissolve_tiles;
+pub use metrics::{ground_resolution, scale_denominator, tile_resolution, tile_size_meters, tile_area_km2, cover_area_km2};
+#[cfg(feature = "mbtiles")]
+pub use mbtiles::{write_mbtiles, read_mbtiles, mbtiles_diff};
+#[cfg(feature = "pmtiles")]
+pub use pmtiles::{PMTilesHeader, PMTilesEntry, read_pmtiles_header, read_pmtiles_entries, pmtiles_diff};
+#[cfg(feature = "wkt")]
+pub use ewkt::{wkt_to_geom, tiles_wkt};
+#[cfg(feature = "wkb")]
+pub use ewkb::{wkb_to_geom, hex_wkb_to_geom, tiles_wkb};
+#[cfg(feature = "geojson")]
+pub use stream::{stream_tiles, stream_tiles_dedup};
 
 const D2R: f64 = PI / 180.0;
 const R2D: f64 = 180.0 / PI;
 
+// WGS84 semi-major axis in meters, the sphere radius of Web Mercator
+const EARTH_RADIUS: f64 = 6_378_137.0;
+
+/**
+ * Non exhaustive, as optional features add their own variants
+ */
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
 pub enum Error {
-    GeomTypeNotSupported
+    GeomTypeNotSupported,
+    /// Reading or writing an MBTiles database failed
+    MBTiles(String),
+    /// A PMTiles archive is malformed or uses an unsupported feature
+    PMTiles(String),
+    /// Reading or writing a file failed
+    Io(String),
+    /// Text is not valid WKT or EWKT
+    WktParse(String),
+    /// Bytes are not valid WKB or EWKB
+    WkbParse(String),
+    /// Geometry is in a spatial reference other than EPSG:4326 or EPSG:3857
+    SridNotSupported(i32),
+    /// A line of a GeoJSON stream could not be parsed or converted
+    GeoJson(String),
+    /// Text is not a valid tile or tile range
+    TileParse(String),
+    /// Zoom is above `MAX_ZOOM`
+    ZoomTooLarge(u8),
+    /// Cover would hold more than the given maximum number of tiles
+    TooManyTiles(usize),
+    /// Zoom is above `ID_MAX_ZOOM`, so its tiles can't be packed into tile IDs
+    IdZoomTooLarge(u8)
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Error {
+        Error::Io(err.to_string())
+    }
+}
+
+/**
+ * Difference between a cover and the tiles present in a tile store
+ */
+#[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TileDiff {
+    /// Tiles of the cover absent from the store
+    pub missing: Vec<(i32, i32, u8)>,
+    /// Tiles in the store that are not part of the cover
+    pub extraneous: Vec<(i32, i32, u8)>
+}
+
+/**
+ * Compare a cover against the tiles present in a tile store
+ *
+ * Only zooms present in the cover are compared, so a store holding a whole
+ * pyramid can be checked one zoom at a time. Both lists are returned sorted
+ */
+pub fn diff_tiles(cover: &[(i32, i32, u8)], existing: &[(i32, i32, u8)]) -> TileDiff {
+    let wanted: HashSet<(i32, i32, u8)> = cover.iter().cloned().collect();
+    let zooms: HashSet<u8> = cover.iter().map(|tile| tile.2).collect();
+    let present: HashSet<(i32, i32, u8)> = existing.iter().filter(|tile| zooms.contains(&tile.2)).cloned().collect();
+
+    let mut diff = TileDiff {
+        missing: wanted.difference(&present).cloned().collect(),
+        extraneous: present.difference(&wanted).cloned().collect()
+    };
+
+    diff.missing.sort_unstable();
+    diff.extraneous.sort_unstable();
+
+    diff
 }
 
 pub fn tiles(geom: &Geometry<f64>, zoom: u8) -> Result<Vec<(i32, i32, u8)>, Error> {
-    match geom {
-        &geo::Geometry::Point(ref point) => {
+    check_zoom(zoom)?;
+
+    match *geom {
+        geo::Geometry::Point(ref point) => {
             Ok(vec!(point_to_tile(point.lng(), point.lat(), zoom)))
         },
-        &geo::Geometry::MultiPoint(ref points) => {
+        geo::Geometry::MultiPoint(ref points) => {
             let mut tiles: Vec<(i32, i32, u8)> = Vec::new();
+            let mut seen: HashSet<(i32, i32, u8)> = HashSet::new();
 
-            for point in points.clone() {
+            for point in points.0.iter() {
                 let tile = point_to_tile(point.lng(), point.lat(), zoom);
-                if !tiles.contains(&tile) {
+                if seen.insert(tile) {
                     tiles.push(tile)
                 }
             }
 
             Ok(tiles)
         },
-        &geo::Geometry::LineString(ref linestring) => {
+        _ => {
             let mut tiles: Vec<(i32, i32, u8)> = Vec::new();
 
-            line_cover(&mut tiles, linestring, zoom, None);
+            geom_cover(&mut tiles, geom, zoom)?;
 
-            tiles.sort();
-            tiles.dedup();
+            sort_dedup(&mut tiles);
 
             Ok(tiles)
-        },
-        &geo::Geometry::MultiLineString(ref linestrings) => {
-            let mut tiles: Vec<(i32, i32, u8)> = Vec::new();
+        }
+    }
+}
 
-            for ref linestring in linestrings.0.iter() {
-                line_cover(&mut tiles, linestring, zoom, None);
+/**
+ * Add the tiles covering a geometry to a list of tiles
+ *
+ * Tiles are appended unsorted and may contain duplicates
+ */
+pub fn geom_cover(tiles: &mut Vec<(i32, i32, u8)>, geom: &Geometry<f64>, zoom: u8) -> Result<(), Error> {
+    check_zoom(zoom)?;
+
+    match *geom {
+        geo::Geometry::Point(ref point) => {
+            tiles.push(point_to_tile(point.lng(), point.lat(), zoom));
+        },
+        geo::Geometry::MultiPoint(ref points) => {
+            for point in points.0.iter() {
+                tiles.push(point_to_tile(point.lng(), point.lat(), zoom));
             }
+        },
+        geo::Geometry::LineString(ref linestring) => {
+            line_cover(tiles, linestring, zoom, None);
+        },
+        geo::Geometry::MultiLineString(ref linestrings) => {
+            cover_members(tiles, &linestrings.0, |tiles, linestring| {
+                line_cover(tiles, linestring, zoom, None);
+            });
+        },
+        geo::Geometry::Polygon(ref polygon) => {
+            poly_cover(tiles, polygon, zoom);
+        },
+        geo::Geometry::MultiPolygon(ref polygons) => {
+            cover_members(tiles, &polygons.0, |tiles, polygon| {
+                poly_cover(tiles, polygon, zoom);
+            });
+        },
+        _ => return Err(Error::GeomTypeNotSupported)
+    }
 
-            tiles.sort();
-            tiles.dedup();
+    Ok(())
+}
 
-            Ok(tiles)
-        },
-        &geo::Geometry::Polygon(ref polygon) => {
-            let mut tiles: Vec<(i32, i32, u8)> = Vec::new();
+/**
+ * Get a fast upper bound on the number of tiles covering a geometry
+ *
+ * Bounded by the tiles of each member's bounding box and, for lines, by the
+ * number of tiles walked between vertices, so no cover is computed
+ */
+pub fn estimate_tile_count(geom: &Geometry<f64>, zoom: u8) -> Result<u64, Error> {
+    check_zoom(zoom)?;
 
-            poly_cover(&mut tiles, polygon, zoom);
+    let estimate = match *geom {
+        geo::Geometry::Point(_) => 1,
+        geo::Geometry::MultiPoint(ref points) => {
+            (points.0.len() as u64).min(bbox_tile_count(points.0.iter().map(|point| point.0), zoom))
+        },
+        geo::Geometry::LineString(ref linestring) => line_estimate(linestring, zoom),
+        geo::Geometry::MultiLineString(ref linestrings) => {
+            let members: u64 = linestrings.0.iter().map(|linestring| line_estimate(linestring, zoom)).sum();
 
-            tiles.sort();
-            tiles.dedup();
+            members.min(bbox_tile_count(linestrings.0.iter().flat_map(|linestring| linestring.0.iter().cloned()), zoom))
+        },
+        geo::Geometry::Polygon(ref polygon) => bbox_tile_count(polygon.exterior().0.iter().cloned(), zoom),
+        geo::Geometry::MultiPolygon(ref polygons) => {
+            let members: u64 = polygons.0.iter().map(|polygon| bbox_tile_count(polygon.exterior().0.iter().cloned(), zoom)).sum();
 
-            Ok(tiles)
+            members.min(bbox_tile_count(polygons.0.iter().flat_map(|polygon| polygon.exterior().0.iter().cloned()), zoom))
         },
-        &geo::Geometry::MultiPolygon(ref polygons) => {
-            let mut tiles: Vec<(i32, i32, u8)> = Vec::new();
+        _ => return Err(Error::GeomTypeNotSupported)
+    };
 
-            for ref polygon in polygons.0.iter() {
-                poly_cover(&mut tiles, polygon, zoom);
-            }
+    Ok(estimate)
+}
 
-            tiles.sort();
-            tiles.dedup();
+// Tiles in the bounding box of a set of coordinates
+fn bbox_tile_count<I: Iterator<Item = Coordinate<f64>>>(coords: I, zoom: u8) -> u64 {
+    match bbox_tile_span(coords, zoom) {
+        Some((width, height)) => width * height,
+        None => 0
+    }
+}
 
-            Ok(tiles)
+// Columns and rows of tiles spanned by a set of coordinates
+fn bbox_tile_span<I: Iterator<Item = Coordinate<f64>>>(coords: I, zoom: u8) -> Option<(u64, u64)> {
+    let mut bounds: Option<(f64, f64, f64, f64)> = None;
+
+    for coord in coords {
+        let (x, y) = clamped_tile_fraction(coord.x, coord.y, zoom);
+        let (x, y) = (x.floor(), y.floor());
+
+        bounds = Some(match bounds {
+            Some((min_x, min_y, max_x, max_y)) => (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+            None => (x, y, x, y)
+        });
+    }
+
+    bounds.map(|(min_x, min_y, max_x, max_y)| ((max_x - min_x + 1.0) as u64, (max_y - min_y + 1.0) as u64))
+}
+
+// Tiles walked by `line_cover`, at most one per tile boundary crossed plus the start of each segment
+fn line_estimate(linestring: &LineString<f64>, zoom: u8) -> u64 {
+    let walked: u64 = linestring.0.windows(2).map(|segment| {
+        let (x0, y0) = clamped_tile_fraction(segment[0].x, segment[0].y, zoom);
+        let (x1, y1) = clamped_tile_fraction(segment[1].x, segment[1].y, zoom);
+
+        ((x1.floor() - x0.floor()).abs() + (y1.floor() - y0.floor()).abs()) as u64 + 1
+    }).sum();
+
+    walked.min(bbox_tile_count(linestring.0.iter().cloned(), zoom))
+}
+
+/**
+ * Get the tiles covering a geometry like `tiles`, failing with
+ * `Error::TooManyTiles` once the cover is known to exceed max_tiles
+ *
+ * Lines and rings are checked against the tiles their extent must cross
+ * before they are walked, polygon interiors are counted before they are
+ * filled and the cover is checked after every member, so an oversized cover
+ * is never fully built
+ */
+pub fn tiles_limited(geom: &Geometry<f64>, zoom: u8, max_tiles: usize) -> Result<Vec<(i32, i32, u8)>, Error> {
+    if estimate_tile_count(geom, zoom)? <= max_tiles as u64 {
+        return tiles(geom, zoom);
+    }
+
+    // distinct tiles so far, so members are counted without re-sorting the cover
+    let mut cover: HashSet<(i32, i32, u8)> = HashSet::new();
+
+    match *geom {
+        geo::Geometry::Point(_) | geo::Geometry::MultiPoint(_) => {
+            let cover = tiles(geom, zoom)?;
+            check_limit(cover.len(), max_tiles)?;
+
+            return Ok(cover);
+        },
+        geo::Geometry::LineString(ref linestring) => {
+            line_cover_limited(&mut cover, linestring, zoom, max_tiles)?;
+        },
+        geo::Geometry::MultiLineString(ref linestrings) => {
+            for linestring in linestrings.0.iter() {
+                line_cover_limited(&mut cover, linestring, zoom, max_tiles)?;
+            }
+        },
+        geo::Geometry::Polygon(ref polygon) => {
+            poly_cover_limited(&mut cover, polygon, zoom, max_tiles)?;
         },
-        _ => Err(Error::GeomTypeNotSupported)
+        geo::Geometry::MultiPolygon(ref polygons) => {
+            for polygon in polygons.0.iter() {
+                poly_cover_limited(&mut cover, polygon, zoom, max_tiles)?;
+            }
+        },
+        _ => return Err(Error::GeomTypeNotSupported)
+    }
+
+    let mut cover: Vec<(i32, i32, u8)> = cover.into_iter().collect();
+    sort_dedup(&mut cover);
+
+    Ok(cover)
+}
+
+fn check_limit(count: usize, max_tiles: usize) -> Result<(), Error> {
+    if count > max_tiles {
+        return Err(Error::TooManyTiles(max_tiles));
+    }
+
+    Ok(())
+}
+
+// A walked line is a 4-connected run of tiles reaching every column and row of its
+// bounding box, so it holds at least width + height - 1 tiles
+fn check_line_span(linestring: &LineString<f64>, zoom: u8, max_tiles: usize) -> Result<(), Error> {
+    if let Some((width, height)) = bbox_tile_span(linestring.0.iter().cloned(), zoom) {
+        if width + height - 1 > max_tiles as u64 {
+            return Err(Error::TooManyTiles(max_tiles));
+        }
+    }
+
+    Ok(())
+}
+
+fn extend_limited(cover: &mut HashSet<(i32, i32, u8)>, tiles: Vec<(i32, i32, u8)>, max_tiles: usize) -> Result<(), Error> {
+    cover.extend(tiles);
+
+    check_limit(cover.len(), max_tiles)
+}
+
+fn line_cover_limited(cover: &mut HashSet<(i32, i32, u8)>, linestring: &LineString<f64>, zoom: u8, max_tiles: usize) -> Result<(), Error> {
+    check_line_span(linestring, zoom, max_tiles)?;
+
+    let mut walked: Vec<(i32, i32, u8)> = Vec::new();
+    line_cover(&mut walked, linestring, zoom, None);
+
+    extend_limited(cover, walked, max_tiles)
+}
+
+fn poly_cover_limited(cover: &mut HashSet<(i32, i32, u8)>, polygon: &Polygon<f64>, zoom: u8, max_tiles: usize) -> Result<(), Error> {
+    check_line_span(polygon.exterior(), zoom, max_tiles)?;
+
+    let mut rings: Vec<(i32, i32, u8)> = Vec::new();
+    let intersections = poly_intersections(&mut rings, polygon, zoom);
+    extend_limited(cover, rings, max_tiles)?;
+
+    // spans never overlap, so the interior alone is a lower bound on the cover
+    let interior: usize = intersections.chunks(2).map(|span| (span[1].0 - span[0].0 - 1).max(0) as usize).sum();
+    if interior > max_tiles {
+        return Err(Error::TooManyTiles(max_tiles));
+    }
+
+    let mut filled: Vec<(i32, i32, u8)> = Vec::new();
+    fill_intersections(&mut filled, &intersections, zoom);
+
+    extend_limited(cover, filled, max_tiles)
+}
+
+/**
+ * Get the highest zoom whose cover of a geometry has at most max_tiles tiles
+ *
+ * Zooms are tried upward from 0 with `tiles_limited`, so no oversized cover is
+ * built. Returns `None` when even the z0 cover is too large
+ */
+pub fn max_zoom_for_budget(geom: &Geometry<f64>, max_tiles: usize) -> Result<Option<u8>, Error> {
+    let mut best: Option<u8> = None;
+
+    for zoom in 0..=MAX_ZOOM {
+        match tiles_limited(geom, zoom, max_tiles) {
+            Ok(_) => best = Some(zoom),
+            Err(Error::TooManyTiles(_)) => break,
+            Err(err) => return Err(err)
+        }
+    }
+
+    Ok(best)
+}
+
+/**
+ * Get the lowest zoom whose tiles, at the given latitude and tile size in
+ * pixels, have a ground resolution of meters_per_pixel or finer
+ *
+ * Clamped to `MAX_ZOOM`
+ */
+pub fn zoom_for_resolution(meters_per_pixel: f64, latitude: f64, tile_size: u32) -> u8 {
+    let z0 = ground_resolution(latitude, 0, tile_size);
+
+    // allow for rounding when the resolution is exactly that of a zoom
+    let zoom = ((z0 / meters_per_pixel).log2() - 1e-9).ceil();
+
+    if zoom.is_nan() || zoom <= 0.0 {
+        0
+    } else {
+        zoom.min(MAX_ZOOM as f64) as u8
+    }
+}
+
+/**
+ * Map of tiles to the IDs of the features touching them
+ */
+pub type TileIndex<K> = HashMap<(i32, i32, u8), Vec<K>>;
+
+/**
+ * Get the features touching each tile for a batch of features
+ *
+ * Each feature is covered into a single reused buffer and only deduplicated
+ * against itself, so no cover is allocated or globally sorted per feature
+ */
+pub fn batch_tiles<K, G, I>(features: I, zoom: u8) -> Result<TileIndex<K>, Error>
+    where I: IntoIterator<Item = (K, G)>, G: Borrow<Geometry<f64>>, K: Clone
+{
+    let mut index: TileIndex<K> = HashMap::new();
+    let mut tiles: Vec<(i32, i32, u8)> = Vec::new();
+
+    for (id, geom) in features {
+        tiles.clear();
+
+        geom_cover(&mut tiles, geom.borrow(), zoom)?;
+
+        tiles.sort_unstable();
+        tiles.dedup();
+
+        for tile in tiles.drain(..) {
+            index.entry(tile).or_default().push(id.clone());
+        }
+    }
+
+    Ok(index)
+}
+
+/**
+ * Get the number of points falling in each tile
+ */
+pub fn point_counts(points: &MultiPoint<f64>, zoom: u8) -> HashMap<(i32, i32, u8), u64> {
+    let mut counts: HashMap<(i32, i32, u8), u64> = HashMap::new();
+
+    for point in points.0.iter() {
+        *counts.entry(point_to_tile(point.lng(), point.lat(), zoom)).or_insert(0) += 1;
+    }
+
+    counts
+}
+
+/**
+ * Get the summed weight of the points falling in each tile
+ */
+pub fn weighted_point_counts<I>(points: I, zoom: u8) -> HashMap<(i32, i32, u8), f64>
+    where I: IntoIterator<Item = (Point<f64>, f64)>
+{
+    let mut counts: HashMap<(i32, i32, u8), f64> = HashMap::new();
+
+    for (point, weight) in points {
+        *counts.entry(point_to_tile(point.lng(), point.lat(), zoom)).or_insert(0.0) += weight;
+    }
+
+    counts
+}
+
+/**
+ * Get the tiles covering a geometry at every zoom from min_zoom to max_zoom
+ *
+ * The cover is computed once at max_zoom and rolled up through the parents
+ * of its tiles. Tiles are returned by ascending zoom, each zoom sorted like
+ * `tiles`
+ */
+pub fn tiles_pyramid(geom: &Geometry<f64>, min_zoom: u8, max_zoom: u8) -> Result<Vec<(i32, i32, u8)>, Error> {
+    if min_zoom > max_zoom {
+        return Ok(Vec::new());
+    }
+
+    let mut level = tiles(geom, max_zoom)?;
+    sort_dedup(&mut level);
+
+    let mut levels: Vec<Vec<(i32, i32, u8)>> = Vec::with_capacity((max_zoom - min_zoom + 1) as usize);
+
+    for _ in min_zoom..max_zoom {
+        let mut parents: Vec<(i32, i32, u8)> = level.iter().map(|tile| get_parent(*tile)).collect();
+        sort_dedup(&mut parents);
+
+        levels.push(level);
+        level = parents;
+    }
+    levels.push(level);
+
+    Ok(levels.into_iter().rev().flatten().collect())
+}
+
+/**
+ * Get the tiles covering a geometry at every zoom like `tiles_pyramid`, computing
+ * each zoom's cover directly
+ *
+ * Slower, but each zoom matches `tiles` exactly. Roll-up can differ for lines
+ * running along tile edges, where rounding picks a different side at each zoom
+ */
+pub fn tiles_pyramid_direct(geom: &Geometry<f64>, min_zoom: u8, max_zoom: u8) -> Result<Vec<(i32, i32, u8)>, Error> {
+    let mut pyramid: Vec<(i32, i32, u8)> = Vec::new();
+
+    for zoom in min_zoom..=max_zoom {
+        let mut level = tiles(geom, zoom)?;
+        sort_dedup(&mut level);
+
+        pyramid.append(&mut level);
+    }
+
+    Ok(pyramid)
+}
+
+/**
+ * Roll per tile counts up through their parents to build a count pyramid
+ *
+ * Every ancestor down to min_zoom holds the sum of its descendants. Input
+ * tiles may be at mixed zooms; those at or below min_zoom are kept as is
+ */
+pub fn count_pyramid<V>(counts: &HashMap<(i32, i32, u8), V>, min_zoom: u8) -> HashMap<(i32, i32, u8), V>
+    where V: Copy + Default + AddAssign
+{
+    let mut pyramid = counts.clone();
+
+    let max_zoom = match counts.keys().map(|tile| tile.2).max() {
+        Some(max_zoom) => max_zoom,
+        None => return pyramid
+    };
+
+    let mut zoom = max_zoom;
+    while zoom > min_zoom {
+        let level: Vec<((i32, i32, u8), V)> = pyramid.iter()
+            .filter(|&(tile, _)| tile.2 == zoom)
+            .map(|(tile, count)| (*tile, *count))
+            .collect();
+
+        for (tile, count) in level {
+            *pyramid.entry(get_parent(tile)).or_default() += count;
+        }
+
+        zoom -= 1;
+    }
+
+    pyramid
+}
+
+/**
+ * Cover each member of a multi geometry into a list of tiles
+ */
+#[cfg(not(feature = "rayon"))]
+fn cover_members<T, F>(tiles: &mut Vec<(i32, i32, u8)>, members: &[T], cover: F)
+    where F: Fn(&mut Vec<(i32, i32, u8)>, &T)
+{
+    for member in members {
+        cover(tiles, member);
     }
 }
 
+/**
+ * Cover each member of a multi geometry in parallel into a list of tiles
+ */
+#[cfg(feature = "rayon")]
+fn cover_members<T, F>(tiles: &mut Vec<(i32, i32, u8)>, members: &[T], cover: F)
+    where T: Sync, F: Fn(&mut Vec<(i32, i32, u8)>, &T) + Sync
+{
+    use rayon::prelude::*;
+
+    let covered = members.par_iter().fold(Vec::new, |mut tiles, member| {
+        cover(&mut tiles, member);
+        tiles
+    }).reduce(Vec::new, |mut tiles, other| {
+        tiles.extend(other);
+        tiles
+    });
+
+    tiles.extend(covered);
+}
+
+#[cfg(not(feature = "rayon"))]
+fn sort_dedup(tiles: &mut Vec<(i32, i32, u8)>) {
+    tiles.sort_unstable();
+    tiles.dedup();
+}
+
+#[cfg(feature = "rayon")]
+fn sort_dedup(tiles: &mut Vec<(i32, i32, u8)>) {
+    use rayon::prelude::*;
+
+    tiles.par_sort_unstable();
+    tiles.dedup();
+}
+
 pub fn poly_cover(tiles: &mut Vec<(i32, i32, u8)>, polygon: &geo::Polygon<f64>, zoom: u8) {
+    let intersections = poly_intersections(tiles, polygon, zoom);
+
+    fill_intersections(tiles, &intersections, zoom);
+}
+
+// Walk a polygon's rings into tiles, returning the row intersections sorted by y, then x
+fn poly_intersections(tiles: &mut Vec<(i32, i32, u8)>, polygon: &geo::Polygon<f64>, zoom: u8) -> Vec<(i32, i32)> {
     let mut intersections: Vec<(i32, i32)> = Vec::new();
 
-    poly_cover_single(&mut intersections, tiles, &polygon.exterior(), zoom);
+    poly_cover_single(&mut intersections, tiles, polygon.exterior(), zoom);
 
     for interior in polygon.interiors() {
-        poly_cover_single(&mut intersections, tiles, &interior, zoom);
+        poly_cover_single(&mut intersections, tiles, interior, zoom);
     }
 
     // sort by y, then x
     intersections.sort_by(|a,b| {
         //Sort by y first
         if a.1 > b.1 {
-            return Ordering::Greater;
+            Ordering::Greater
         } else if a.1 < b.1 {
-            return Ordering::Less;
+            Ordering::Less
         } else if a.0 > b.0 {
-            return Ordering::Greater;
+            Ordering::Greater
         } else if a.0 < b.0 {
-            return Ordering::Less;
+            Ordering::Less
         } else {
-            return Ordering::Equal
+            Ordering::Equal
         }
     });
 
+    intersections
+}
+
+#[cfg(not(feature = "rayon"))]
+fn fill_intersections(tiles: &mut Vec<(i32, i32, u8)>, intersections: &[(i32, i32)], zoom: u8) {
+    fill_spans(tiles, intersections, zoom);
+}
+
+/**
+ * Fill a polygon's interior in parallel, one band of rows per task
+ *
+ * Intersections are sorted by row, so fixed size chunks of whole spans are
+ * contiguous row bands. Bands are appended in order, keeping output deterministic
+ */
+#[cfg(feature = "rayon")]
+fn fill_intersections(tiles: &mut Vec<(i32, i32, u8)>, intersections: &[(i32, i32)], zoom: u8) {
+    use rayon::prelude::*;
+
+    const BAND_SPANS: usize = 256;
+
+    if intersections.len() <= 2 * BAND_SPANS {
+        return fill_spans(tiles, intersections, zoom);
+    }
+
+    let bands: Vec<Vec<(i32, i32, u8)>> = intersections.par_chunks(2 * BAND_SPANS).map(|band| {
+        let mut filled: Vec<(i32, i32, u8)> = Vec::new();
+        fill_spans(&mut filled, band, zoom);
+        filled
+    }).collect();
+
+    for band in bands {
+        tiles.extend(band);
+    }
+}
+
+fn fill_spans(tiles: &mut Vec<(i32, i32, u8)>, intersections: &[(i32, i32)], zoom: u8) {
     let mut int_it = 0;
     while int_it < intersections.len() {
         // fill tiles between pairs of intersections
@@ -112,19 +683,19 @@ pub fn poly_cover(tiles: &mut Vec<(i32, i32, u8)>, polygon: &geo::Polygon<f64>,
         while x < intersections[int_it + 1].0 {
             tiles.push((x, y, zoom));
 
-            x = x + 1;
+            x += 1;
         }
 
-        int_it = int_it + 2;
+        int_it += 2;
     }
 }
 
 fn poly_cover_single(intersections: &mut Vec<(i32, i32)>, tiles: &mut Vec<(i32, i32, u8)>, linestring: &geo::LineString<f64>, zoom: u8) {
     let mut ring: Vec<(i32, i32)> = Vec::new();
 
-    line_cover(tiles, &linestring, zoom, Some(&mut ring));
+    line_cover(tiles, linestring, zoom, Some(&mut ring));
 
-    if ring.len() > 0 {
+    if !ring.is_empty() {
         let mut j = 0;
         let len = ring.len();
         let mut k = len - 1;
@@ -140,7 +711,7 @@ fn poly_cover_single(intersections: &mut Vec<(i32, i32)>, tiles: &mut Vec<(i32,
             }
 
             k = j;
-            j = j + 1;
+            j += 1;
         }
     }
 }
@@ -148,13 +719,17 @@ fn poly_cover_single(intersections: &mut Vec<(i32, i32)>, tiles: &mut Vec<(i32,
 pub fn line_cover(tiles: &mut Vec<(i32, i32, u8)>, linestring: &geo::LineString<f64>, zoom: u8, mut ring: Option<&mut Vec<(i32, i32)>>) {
     let mut prev_x: Option<f64> = None;
     let mut prev_y: Option<f64> = None;
-    let mut x: f64 = 0.0;
     let mut y: f64 = 0.0;
 
+    // empty lines, as parsed from WKT & WKB, have no segments to walk
+    if linestring.0.len() < 2 {
+        return;
+    }
+
     let mut i = 0;
     while i < linestring.0.len() - 1 {
-        let start = point_to_tile_fraction(linestring.0[i].x, linestring.0[i].y, zoom);
-        let stop = point_to_tile_fraction(linestring.0[i + 1].x, linestring.0[i + 1].y, zoom);
+        let start = clamped_tile_fraction(linestring.0[i].x, linestring.0[i].y, zoom);
+        let stop = clamped_tile_fraction(linestring.0[i + 1].x, linestring.0[i + 1].y, zoom);
 
         let x0 = start.0;
         let y0 = start.1;
@@ -166,24 +741,27 @@ pub fn line_cover(tiles: &mut Vec<(i32, i32, u8)>, linestring: &geo::LineString<
         let dy = y1 - y0;
 
         if dy == 0.0 && dx == 0.0 {
-            i = i + 1;
+            i += 1;
             continue;
         }
 
         let sx = if dx > 0.0 { 1.0 } else { -1.0 };
         let sy = if dy > 0.0 { 1.0 } else { -1.0 };
 
-        x = x0.floor();
+        let mut x = x0.floor();
         y = y0.floor();
 
+        let end_x = x1.floor();
+        let end_y = y1.floor();
+
         let mut t_max_x = if dx == 0.0 {
-            INF
+            f64::INFINITY
         } else {
             (((if dx > 0.0 { 1.0 } else { 0.0 }) + x - x0) / dx).abs()
         };
 
         let mut t_max_y = if dy == 0.0 {
-            INF
+            f64::INFINITY
         } else {
             (((if dy > 0.0 { 1.0 } else { 0.0 }) + y - y0) / dy).abs()
         };
@@ -194,53 +772,76 @@ pub fn line_cover(tiles: &mut Vec<(i32, i32, u8)>, linestring: &geo::LineString<
         if Some(x) != prev_x || Some(y) != prev_y {
             tiles.push((x as i32, y as i32, zoom));
 
-            if ring != None && Some(y) != prev_y {
-                match ring {
-                    Some(ref mut r) => r.push((x as i32, y as i32)),
-                    _ => ()
-                };
+            if Some(y) != prev_y {
+                if let Some(ref mut r) = ring {
+                    r.push((x as i32, y as i32));
+                }
             }
 
             prev_x = Some(x);
             prev_y = Some(y);
         }
 
-        while t_max_x < 1.0 || t_max_y < 1.0 {
+        // stop once the end tile is reached so rounding in t_max can't overshoot it
+        while (t_max_x < 1.0 || t_max_y < 1.0) && (x != end_x || y != end_y) {
             if t_max_x < t_max_y {
-                t_max_x = t_max_x + tdx;
-                x = x + sx;
+                t_max_x += tdx;
+                x += sx;
             } else {
-                t_max_y = t_max_y + tdy;
-                y = y + sy;
+                t_max_y += tdy;
+                y += sy;
             }
 
             tiles.push((x as i32, y as i32, zoom));
 
-            if ring != None && Some(y) != prev_y {
-                match ring {
-                    Some(ref mut r) => r.push((x as i32, y as i32)),
-                    _ => ()
-                };
+            if Some(y) != prev_y {
+                if let Some(ref mut r) = ring {
+                    r.push((x as i32, y as i32));
+                }
             }
             prev_x = Some(x);
             prev_y = Some(y);
         }
 
-        i = i + 1;
+        i += 1;
     }
 
-    if ring != None {
-        match ring {
-            Some(ref mut r) => {
-                if r.len() > 0 && y as i32 == r[0].1 {
-                    r.pop();
-                }
-            },
-            _ => ()
+    if let Some(ref mut r) = ring {
+        if !r.is_empty() && y as i32 == r[0].1 {
+            r.pop();
         }
     }
 }
 
+/**
+ * Deepest zoom a tile may have
+ *
+ * Tile coordinates are i32, so this leaves room for the children of any valid
+ * tile without overflowing
+ */
+pub const MAX_ZOOM: u8 = 30;
+
+fn check_zoom(zoom: u8) -> Result<(), Error> {
+    if zoom > MAX_ZOOM {
+        return Err(Error::ZoomTooLarge(zoom));
+    }
+
+    Ok(())
+}
+
+/**
+ * Whether a tile is at most `MAX_ZOOM` with x & y within `0..2^z`
+ */
+pub fn is_valid(tile: (i32, i32, u8)) -> bool {
+    if tile.2 > MAX_ZOOM {
+        return false;
+    }
+
+    let z2 = 1_i32 << tile.2;
+
+    tile.0 >= 0 && tile.1 >= 0 && tile.0 < z2 && tile.1 < z2
+}
+
 pub fn get_children(tile: (i32, i32, u8)) -> Vec<(i32, i32, u8)> {
     vec![
         (tile.0 * 2, tile.1 * 2, tile.2 + 1),
@@ -250,71 +851,794 @@ pub fn get_children(tile: (i32, i32, u8)) -> Vec<(i32, i32, u8)> {
     ]
 }
 
-pub fn get_parent(tile: (i32, i32, u8)) -> (i32, i32, u8) {
-    (tile.0 >> 1, tile.1 >> 1, tile.2 - 1)
+/**
+ * Get the parent of a tile, or `None` for the z0 tile
+ */
+pub fn parent(tile: (i32, i32, u8)) -> Option<(i32, i32, u8)> {
+    if tile.2 == 0 {
+        None
+    } else {
+        Some(get_parent(tile))
+    }
+}
+
+/**
+ * Get the parent of a tile
+ *
+ * Panics on overflow for the z0 tile, see `parent` for a fallible version
+ */
+pub fn get_parent(tile: (i32, i32, u8)) -> (i32, i32, u8) {
+    (tile.0 >> 1, tile.1 >> 1, tile.2 - 1)
+}
+
+pub fn get_siblings(tile: (i32, i32, u8)) -> Vec<(i32, i32, u8)> {
+    get_children(get_parent(tile))
+}
+
+/**
+ * Get every ancestor of a tile, from its parent up to the z0 tile
+ */
+pub fn ancestors(tile: (i32, i32, u8)) -> Vec<(i32, i32, u8)> {
+    (0..tile.2).rev().map(|z| {
+        let shift = tile.2 - z;
+        (tile.0 >> shift, tile.1 >> shift, z)
+    }).collect()
+}
+
+/**
+ * Get the descendants of a tile at the given zoom
+ *
+ * Tiles are yielded sorted by x, then y. A zoom below the tile's or above
+ * `MAX_ZOOM` yields nothing, the tile's own zoom yields the tile itself
+ */
+pub fn descendants(tile: (i32, i32, u8), zoom: u8) -> TileRange {
+    if zoom < tile.2 || zoom > MAX_ZOOM {
+        return TileRange { min_y: 0, max_x: 0, max_y: 0, x: 1, y: 0, zoom };
+    }
+
+    let shift = zoom - tile.2;
+    let (min_x, min_y) = (tile.0 << shift, tile.1 << shift);
+
+    tile_range(min_x, min_y, min_x + (1 << shift) - 1, min_y + (1 << shift) - 1, zoom)
+}
+
+// Iterate the inclusive block of tiles between the min & max x and y
+fn tile_range(min_x: i32, min_y: i32, max_x: i32, max_y: i32, zoom: u8) -> TileRange {
+    TileRange { min_y, max_x, max_y, x: min_x, y: min_y, zoom }
+}
+
+/**
+ * Iterator over an inclusive x & y range of tiles at one zoom, sorted by x, then y
+ */
+#[derive(Debug, Clone)]
+pub struct TileRange {
+    min_y: i32,
+    max_x: i32,
+    max_y: i32,
+    x: i32,
+    y: i32,
+    zoom: u8
+}
+
+impl TileRange {
+    /**
+     * Inclusive x & y range of the remaining tiles
+     *
+     * Returned in the format [ Min X, Min Y, Max X, Max Y ]
+     */
+    pub fn range(&self) -> (i32, i32, i32, i32) {
+        (self.x, self.min_y, self.max_x, self.max_y)
+    }
+}
+
+impl Iterator for TileRange {
+    type Item = (i32, i32, u8);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.x > self.max_x {
+            return None;
+        }
+
+        let tile = (self.x, self.y, self.zoom);
+
+        if self.y == self.max_y {
+            self.y = self.min_y;
+            self.x += 1;
+        } else {
+            self.y += 1;
+        }
+
+        Some(tile)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.x > self.max_x {
+            return (0, Some(0));
+        }
+
+        let height = (self.max_y - self.min_y) as u64 + 1;
+        let remaining = (self.max_x - self.x) as u64 * height + (self.max_y - self.y) as u64 + 1;
+
+        if remaining > usize::MAX as u64 {
+            (usize::MAX, None)
+        } else {
+            (remaining as usize, Some(remaining as usize))
+        }
+    }
+}
+
+/**
+ * Whether `ancestor` contains `tile` at a higher zoom
+ *
+ * A tile is not its own ancestor
+ */
+pub fn is_ancestor_of(ancestor: (i32, i32, u8), tile: (i32, i32, u8)) -> bool {
+    if ancestor.2 >= tile.2 {
+        return false;
+    }
+
+    let shift = tile.2 - ancestor.2;
+
+    (tile.0 >> shift, tile.1 >> shift) == (ancestor.0, ancestor.1)
+}
+
+/**
+ * Get the highest zoom tile containing every given tile
+ *
+ * This is one of the tiles when it contains the others. Returns `None` when
+ * no tiles are given
+ */
+pub fn common_ancestor(tiles: &[(i32, i32, u8)]) -> Option<(i32, i32, u8)> {
+    let mut common = *tiles.first()?;
+
+    for tile in tiles[1..].iter() {
+        // bring both to the lower of the two zooms, then climb until they meet
+        let z = common.2.min(tile.2);
+        let mut a = (common.0 >> (common.2 - z), common.1 >> (common.2 - z));
+        let mut b = (tile.0 >> (tile.2 - z), tile.1 >> (tile.2 - z));
+
+        let mut zoom = z;
+        while a != b {
+            a = (a.0 >> 1, a.1 >> 1);
+            b = (b.0 >> 1, b.1 >> 1);
+            zoom -= 1;
+        }
+
+        common = (a.0, a.1, zoom);
+    }
+
+    Some(common)
+}
+
+/**
+ * Get the quadkey of a tile
+ *
+ * Panics when the zoom is above `MAX_ZOOM`
+ */
+pub fn tile_to_quadkey(tile: (i32, i32, u8)) -> String {
+    assert!(tile.2 <= MAX_ZOOM, "zoom {} is above MAX_ZOOM", tile.2);
+
+    let mut quadkey = String::with_capacity(tile.2 as usize);
+
+    let mut z = tile.2;
+    while z > 0 {
+        let mask = 1 << (z - 1);
+        let mut digit = b'0';
+
+        if tile.0 & mask != 0 {
+            digit += 1;
+        }
+        if tile.1 & mask != 0 {
+            digit += 2;
+        }
+
+        quadkey.push(digit as char);
+        z -= 1;
+    }
+
+    quadkey
+}
+
+/**
+ * Get the tile for a quadkey, or None if it contains characters other than 0-3
+ * or is longer than `MAX_ZOOM` digits
+ */
+pub fn quadkey_to_tile(quadkey: &str) -> Option<(i32, i32, u8)> {
+    if quadkey.len() > MAX_ZOOM as usize {
+        return None;
+    }
+
+    let mut x = 0;
+    let mut y = 0;
+
+    for digit in quadkey.bytes() {
+        x <<= 1;
+        y <<= 1;
+
+        match digit {
+            b'0' => (),
+            b'1' => x |= 1,
+            b'2' => y |= 1,
+            b'3' => {
+                x |= 1;
+                y |= 1;
+            },
+            _ => return None
+        }
+    }
+
+    Some((x, y, quadkey.len() as u8))
+}
+
+/**
+ * Get the metatile containing a tile
+ *
+ * Metatiles are blocks of size x size tiles, identified by their north west
+ * tile as in mod_tile/renderd
+ */
+pub fn tile_to_metatile(tile: (i32, i32, u8), size: u32) -> (i32, i32, u8) {
+    assert!(size > 0, "metatile size must be positive");
+
+    let size = size as i32;
+
+    (tile.0 - tile.0 % size, tile.1 - tile.1 % size, tile.2)
+}
+
+/**
+ * Get the member tiles of a metatile
+ *
+ * At zooms where the world is narrower than the metatile, only the tiles
+ * that exist at that zoom are returned
+ */
+pub fn metatile_tiles(metatile: (i32, i32, u8), size: u32) -> Vec<(i32, i32, u8)> {
+    let size = size as i64;
+    let z2: i64 = 1 << metatile.2;
+
+    let x_max = (metatile.0 as i64 + size).min(z2) as i32;
+    let y_max = (metatile.1 as i64 + size).min(z2) as i32;
+
+    let mut tiles: Vec<(i32, i32, u8)> = Vec::new();
+    for x in metatile.0..x_max {
+        for y in metatile.1..y_max {
+            tiles.push((x, y, metatile.2));
+        }
+    }
+
+    tiles
+}
+
+/**
+ * Get the sorted, distinct metatiles containing a list of tiles
+ */
+pub fn metatiles(cover: &[(i32, i32, u8)], size: u32) -> Vec<(i32, i32, u8)> {
+    let mut metatiles: Vec<(i32, i32, u8)> = cover.iter().map(|tile| tile_to_metatile(*tile, size)).collect();
+
+    metatiles.sort_unstable();
+    metatiles.dedup();
+
+    metatiles
+}
+
+/**
+ * Get every member tile of the metatiles covering a geometry
+ */
+pub fn tiles_metatiled(geom: &Geometry<f64>, zoom: u8, size: u32) -> Result<Vec<(i32, i32, u8)>, Error> {
+    let mut tiles: Vec<(i32, i32, u8)> = metatiles(&tiles(geom, zoom)?, size).into_iter()
+        .flat_map(|metatile| metatile_tiles(metatile, size))
+        .collect();
+
+    tiles.sort_unstable();
+
+    Ok(tiles)
+}
+
+/**
+ * Get the BBOX of a tile
+ *
+ * Returned in the format [ West, South, East, North ]
+ */
+pub fn tile_to_bbox(tile: (i32, i32, u8)) -> (f64, f64, f64, f64) {
+    (
+        tile_to_lon(tile.0, tile.2),
+        tile_to_lat(tile.1 + 1, tile.2),
+        tile_to_lon(tile.0 + 1, tile.2),
+        tile_to_lat(tile.1, tile.2)
+    )
+}
+
+/**
+ * Get the center of a tile
+ *
+ * The center is halfway across the tile in Web Mercator, so slightly poleward
+ * of the middle of its latitudes
+ *
+ * Returned in the format [ Lon, Lat ]
+ */
+pub fn tile_center(tile: (i32, i32, u8)) -> (f64, f64) {
+    (
+        tile_fraction_to_lon(tile.0 as f64 + 0.5, tile.2),
+        tile_fraction_to_lat(tile.1 as f64 + 0.5, tile.2)
+    )
+}
+
+/**
+ * Get the corners of a tile, clockwise from the north west
+ *
+ * Returned in the format [ [ Lon, Lat ]; 4 ] for the NW, NE, SE & SW corners
+ */
+pub fn tile_corners(tile: (i32, i32, u8)) -> [(f64, f64); 4] {
+    let (west, south, east, north) = tile_to_bbox(tile);
+
+    [(west, north), (east, north), (east, south), (west, south)]
+}
+
+/**
+ * Get the outline of a tile as a polygon, wound counter-clockwise
+ */
+pub fn tile_to_polygon(tile: (i32, i32, u8)) -> Polygon<f64> {
+    let (west, south, east, north) = tile_to_bbox(tile);
+
+    Polygon::new(LineString::from(vec![
+        (west, south),
+        (east, south),
+        (east, north),
+        (west, north),
+        (west, south)
+    ]), vec![])
+}
+
+/**
+ * Get the extent of a tile as a rectangle
+ */
+pub fn tile_to_rect(tile: (i32, i32, u8)) -> Rect<f64> {
+    let (west, south, east, north) = tile_to_bbox(tile);
+
+    Rect::new(Coordinate { x: west, y: south }, Coordinate { x: east, y: north })
+}
+
+/**
+ * Get the longitudinal value for a given tile corner
+ */
+pub fn tile_to_lon(x: i32, z: u8) -> f64 {
+    tile_fraction_to_lon(x as f64, z)
+}
+
+
+/**
+ * Get the latitudinal value for a given tile corner
+ */
+pub fn tile_to_lat(y: i32, z: u8) -> f64 {
+    tile_fraction_to_lat(y as f64, z)
+}
+
+pub(crate) fn tile_fraction_to_lon(x: f64, z: u8) -> f64 {
+    x / 2.0_f64.powi(z as i32) * 360.0 - 180.0
+}
+
+pub(crate) fn tile_fraction_to_lat(y: f64, z: u8) -> f64 {
+    let n: f64 = PI - 2.0 * PI * y / 2.0_f64.powi(z as i32);
+    R2D * (0.5 * (n.exp() - (-n).exp())).atan()
+}
+
+/**
+ *  * Get the tile for a point at a specified zoom level
+ */
+pub fn point_to_tile(lon: f64, lat: f64, z: u8) -> (i32, i32, u8) {
+    let tile_frac = point_to_tile_fraction(lon, lat, z);
+
+    (tile_frac.0.floor() as i32, tile_frac.1.floor() as i32, tile_frac.2)
+}
+
+/**
+ *  * Get the precise fractional tile location for a point at a zoom level
+ *
+ * Latitudes are clamped to the Web Mercator limits of +/- MAX_LAT, so the
+ * poles fall on the top and bottom rows of tiles
+ */
+pub fn point_to_tile_fraction(lon: f64, lat: f64, z: u8) -> (f64, f64, u8) {
+    let z2: f64 = 2.0_f64.powf(z as f64);
+    let (mut x, y) = unwrapped_tile_fraction(lon, lat, z);
+
+    // Wrap Tile X
+    x %= z2;
+    if x < 0.0 {
+        x += z2
+    }
+
+    (clamp_to_grid(x, z2), y, z)
+}
+
+/**
+ * Latitude limit of the Web Mercator projection
+ *
+ * The latitude at which the square world map ends, `atan(sinh(PI))`
+ */
+pub const MAX_LAT: f64 = 85.051_128_779_806_6;
+
+// Fractional tile location without wrapping x around the antimeridian
+fn unwrapped_tile_fraction(lon: f64, lat: f64, z: u8) -> (f64, f64) {
+    let sin = (lat.clamp(-MAX_LAT, MAX_LAT) * D2R).sin();
+    let base: f64 = 2.0;
+
+    let z2: f64 = base.powf(z as f64);
+    let x = z2 * (lon / 360.0 + 0.5);
+    let y = z2 * (0.5 - 0.25 * ((1.0 + sin) / (1.0 - sin)).ln() / PI);
+
+    (x, clamp_to_grid(y, z2))
+}
+
+/**
+ * Fractional tile location with x clamped to the grid rather than wrapped
+ *
+ * Used when walking lines and rings, so vertices on the antimeridian or the
+ * poles land in the outermost column or row instead of jumping across the grid
+ */
+fn clamped_tile_fraction(lon: f64, lat: f64, z: u8) -> (f64, f64) {
+    let z2: f64 = 2.0_f64.powf(z as f64);
+    let (x, y) = unwrapped_tile_fraction(lon, lat, z);
+
+    (clamp_to_grid(x, z2), y)
+}
+
+// Clamp a fractional tile coordinate to [0, z2) so it always floors to a valid tile
+fn clamp_to_grid(v: f64, z2: f64) -> f64 {
+    v.clamp(0.0, z2 * (1.0 - f64::EPSILON))
+}
+
+/**
+ * Get the tile-local integer coordinates of a point for a tile with the given
+ * extent, e.g. 4096 for Mapbox Vector Tiles
+ *
+ * Coordinates run from the tile's north west corner and fall outside
+ * 0..extent for points beyond the tile
+ */
+pub fn point_to_tile_pixel(lon: f64, lat: f64, tile: (i32, i32, u8), extent: u32) -> (i32, i32) {
+    let (x, y) = unwrapped_tile_fraction(lon, lat, tile.2);
+
+    (
+        ((x - tile.0 as f64) * extent as f64).round() as i32,
+        ((y - tile.1 as f64) * extent as f64).round() as i32
+    )
+}
+
+/**
+ * Get the lon/lat of tile-local coordinates for a tile with the given extent
+ *
+ * With the extent set to the tile size in pixels, e.g. 256 or 512, this maps
+ * pixels of a rendered tile, so `(0.5, 0.5)` is the center of its first pixel
+ *
+ * Returned in the format [ Lon, Lat ]
+ */
+pub fn tile_pixel_to_point(x: f64, y: f64, tile: (i32, i32, u8), extent: u32) -> (f64, f64) {
+    (
+        tile_fraction_to_lon(tile.0 as f64 + x / extent as f64, tile.2),
+        tile_fraction_to_lat(tile.1 as f64 + y / extent as f64, tile.2)
+    )
+}
+
+/**
+ * Convert a lon/lat geometry into tile-local integer coordinates
+ */
+pub fn geom_to_tile_pixels(geom: &Geometry<f64>, tile: (i32, i32, u8), extent: u32) -> Geometry<i32> {
+    geom.map_coords(|&(lon, lat)| point_to_tile_pixel(lon, lat, tile, extent))
+}
+
+/**
+ * Convert a geometry in tile-local integer coordinates back into lon/lat
+ */
+pub fn tile_pixels_to_geom(geom: &Geometry<i32>, tile: (i32, i32, u8), extent: u32) -> Geometry<f64> {
+    geom.map_coords(|&(x, y)| tile_pixel_to_point(x as f64, y as f64, tile, extent))
+}
+
+/**
+ * Convert Web Mercator (EPSG:3857) meters into lon/lat
+ *
+ * Returned in the format [ Lon, Lat ]
+ */
+pub fn mercator_to_lon_lat(x: f64, y: f64) -> (f64, f64) {
+    (
+        R2D * x / EARTH_RADIUS,
+        R2D * (2.0 * (y / EARTH_RADIUS).exp().atan() - PI / 2.0)
+    )
+}
+
+/**
+ * Convert lon/lat into Web Mercator (EPSG:3857) meters
+ *
+ * Returned in the format [ X, Y ]
+ */
+pub fn lon_lat_to_mercator(lon: f64, lat: f64) -> (f64, f64) {
+    (
+        EARTH_RADIUS * lon * D2R,
+        EARTH_RADIUS * (PI / 4.0 + lat.clamp(-MAX_LAT, MAX_LAT) * D2R / 2.0).tan().ln()
+    )
+}
+
+/**
+ * Bring a geometry in the given SRID into lon/lat
+ */
+#[cfg(any(feature = "wkt", feature = "wkb"))]
+fn geom_from_srid(geom: Geometry<f64>, srid: i32) -> Result<Geometry<f64>, Error> {
+    match srid {
+        4326 => Ok(geom),
+        3857 | 900913 => Ok(geom.map_coords(|&(x, y)| mercator_to_lon_lat(x, y))),
+        _ => Err(Error::SridNotSupported(srid))
+    }
+}
+
+/**
+ * Highest zoom that can be packed into a tile ID
+ *
+ * 5 bits of zoom + 2 bits per zoom level of interleaved x/y, leaving the
+ * sign bit clear so IDs also fit in signed 64-bit database columns
+ */
+pub const ID_MAX_ZOOM: u8 = 29;
+
+const ID_ZOOM_SHIFT: u64 = 58;
+
+/**
+ * Get the packed ID of a tile
+ *
+ * The zoom is stored in the top bits, followed by the Morton (Z-order)
+ * interleave of x and y. Sorting IDs groups tiles by zoom and, within a zoom,
+ * keeps all descendants of a tile in one contiguous range (see `id_range`).
+ * Passing a tile outside its zoom's grid is a bug in the caller, checked in
+ * debug builds, and yields an ID of some other tile
+ */
+pub fn tile_to_id(tile: (i32, i32, u8)) -> u64 {
+    assert!(tile.2 <= ID_MAX_ZOOM, "zoom {} cannot be packed into a tile ID", tile.2);
+    debug_assert!(is_valid(tile), "tile {:?} is outside the grid of its zoom", tile);
+
+    ((tile.2 as u64) << ID_ZOOM_SHIFT) | morton_encode(tile.0 as u32, tile.1 as u32)
+}
+
+/**
+ * Get the tile for a packed ID created by `tile_to_id`
+ */
+pub fn id_to_tile(id: u64) -> (i32, i32, u8) {
+    let z = (id >> ID_ZOOM_SHIFT) as u8;
+    let (x, y) = morton_decode(id & ((1 << ID_ZOOM_SHIFT) - 1));
+
+    (x as i32, y as i32, z)
+}
+
+/**
+ * Get the inclusive range of IDs covering every descendant of a tile at a
+ * given zoom
+ *
+ * Returned in the format [ First, Last ]
+ */
+pub fn id_range(tile: (i32, i32, u8), zoom: u8) -> (u64, u64) {
+    assert!(zoom >= tile.2, "zoom {} is above tile zoom {}", zoom, tile.2);
+    assert!(zoom <= ID_MAX_ZOOM, "zoom {} cannot be packed into a tile ID", zoom);
+
+    let shift = 2 * (zoom - tile.2) as u64;
+    let first = ((zoom as u64) << ID_ZOOM_SHIFT) | (morton_encode(tile.0 as u32, tile.1 as u32) << shift);
+
+    (first, first | ((1 << shift) - 1))
+}
+
+/**
+ * Get the sorted packed IDs of the tiles covering a geometry
+ *
+ * Fails with `Error::IdZoomTooLarge` above `ID_MAX_ZOOM`
+ */
+pub fn tile_ids(geom: &Geometry<f64>, zoom: u8) -> Result<Vec<u64>, Error> {
+    if zoom > ID_MAX_ZOOM {
+        return Err(Error::IdZoomTooLarge(zoom));
+    }
+
+    let mut ids: Vec<u64> = tiles(geom, zoom)?.into_iter().map(tile_to_id).collect();
+
+    ids.sort_unstable();
+
+    Ok(ids)
+}
+
+fn morton_encode(x: u32, y: u32) -> u64 {
+    spread_bits(x) | (spread_bits(y) << 1)
+}
+
+fn morton_decode(code: u64) -> (u32, u32) {
+    (compact_bits(code), compact_bits(code >> 1))
+}
+
+// Insert a zero bit between each of the bits of v
+fn spread_bits(v: u32) -> u64 {
+    let mut v = v as u64;
+
+    v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+    v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+    v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+    (v | (v << 1)) & 0x5555_5555_5555_5555
+}
+
+// Inverse of spread_bits, dropping every odd bit
+fn compact_bits(v: u64) -> u32 {
+    let mut v = v & 0x5555_5555_5555_5555;
+
+    v = (v | (v >> 1)) & 0x3333_3333_3333_3333;
+    v = (v | (v >> 2)) & 0x0F0F_0F0F_0F0F_0F0F;
+    v = (v | (v >> 4)) & 0x00FF_00FF_00FF_00FF;
+    v = (v | (v >> 8)) & 0x0000_FFFF_0000_FFFF;
+    ((v | (v >> 16)) & 0x0000_0000_FFFF_FFFF) as u32
+}
+
+/**
+ * Order in which to return the tiles of a cover
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Order {
+    /// Sorted by x, then y, then zoom - the order returned by `tiles`
+    Lexicographic,
+    /// Sorted by zoom, then along the Z-order curve - the order of `tile_to_id`
+    Morton,
+    /// Sorted by zoom, then along the Hilbert curve - the order of `tile_to_pmtiles_id`
+    Hilbert
+}
+
+/**
+ * Get the tiles covering a geometry, sorted in the given order
+ */
+pub fn tiles_ordered(geom: &Geometry<f64>, zoom: u8, order: Order) -> Result<Vec<(i32, i32, u8)>, Error> {
+    let mut tiles = tiles(geom, zoom)?;
+
+    sort_tiles(&mut tiles, order);
+
+    Ok(tiles)
+}
+
+/**
+ * Sort a list of tiles in place
+ */
+pub fn sort_tiles(tiles: &mut [(i32, i32, u8)], order: Order) {
+    match order {
+        Order::Lexicographic => tiles.sort_unstable(),
+        Order::Morton => tiles.sort_unstable_by_key(|tile| (tile.2, morton_encode(tile.0 as u32, tile.1 as u32))),
+        Order::Hilbert => tiles.sort_unstable_by_key(|tile| tile_to_pmtiles_id(*tile))
+    }
+}
+
+/**
+ * Get the position of a tile along the Hilbert curve filling its zoom level
+ *
+ * Returns a value in the range 0..4^z
+ */
+pub fn tile_to_hilbert(tile: (i32, i32, u8)) -> u64 {
+    assert!(tile.2 <= MAX_ZOOM, "zoom {} is above MAX_ZOOM", tile.2);
+
+    let n: u64 = 1 << tile.2;
+    let mut x = tile.0 as u64;
+    let mut y = tile.1 as u64;
+    let mut d: u64 = 0;
+
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = if x & s > 0 { 1 } else { 0 };
+        let ry = if y & s > 0 { 1 } else { 0 };
+
+        d += s * s * ((3 * rx) ^ ry);
+
+        hilbert_rotate(n, &mut x, &mut y, rx, ry);
+
+        s /= 2;
+    }
+
+    d
+}
+
+/**
+ * Get the tile at a position along the Hilbert curve filling a zoom level
+ */
+pub fn hilbert_to_tile(d: u64, z: u8) -> (i32, i32, u8) {
+    assert!(z <= MAX_ZOOM, "zoom {} is above MAX_ZOOM", z);
+
+    let n: u64 = 1 << z;
+    let mut x: u64 = 0;
+    let mut y: u64 = 0;
+    let mut t = d;
+
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+
+        hilbert_rotate(s, &mut x, &mut y, rx, ry);
+
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+
+    (x as i32, y as i32, z)
 }
 
-pub fn get_siblings(tile: (i32, i32, u8)) -> Vec<(i32, i32, u8)> {
-    get_children(get_parent(tile))
+fn hilbert_rotate(n: u64, x: &mut u64, y: &mut u64, rx: u64, ry: u64) {
+    if ry == 0 {
+        if rx == 1 {
+            *x = n - 1 - *x;
+            *y = n - 1 - *y;
+        }
+
+        std::mem::swap(x, y);
+    }
 }
 
 /**
- * Get the BBOX of a tile
+ * Get the PMTiles v3 tile ID of a tile
  *
- * Returned in the format [ West, South, East, North ]
+ * The number of tiles in all lower zooms plus the Hilbert position of the
+ * tile within its own zoom
  */
-pub fn tile_to_bbox(tile: (i32, i32, u8)) -> (f64, f64, f64, f64) {
-    (
-        tile_to_lon(tile.0, tile.2),
-        tile_to_lat(tile.1 + 1, tile.2),
-        tile_to_lon(tile.0 + 1, tile.2),
-        tile_to_lat(tile.1, tile.2)
-    )
+pub fn tile_to_pmtiles_id(tile: (i32, i32, u8)) -> u64 {
+    pmtiles_zoom_offset(tile.2) + tile_to_hilbert(tile)
 }
 
 /**
- * Get the longitudinal value for a given tile corner
+ * Get the tile for a PMTiles v3 tile ID
+ *
+ * Panics for IDs beyond the last tile of `MAX_ZOOM`
  */
-pub fn tile_to_lon(x: i32, z: u8) -> f64 {
-    x as f64 / (2.0 as f64).powi(z as i32) * 360.0 - 180.0
+pub fn pmtiles_id_to_tile(id: u64) -> (i32, i32, u8) {
+    assert!(id < pmtiles_zoom_offset(MAX_ZOOM + 1), "tile ID {} is above MAX_ZOOM", id);
+
+    let mut z: u8 = 0;
+    let mut offset: u64 = 0;
+
+    loop {
+        let count: u64 = 1 << (2 * z as u64);
+
+        if id < offset + count {
+            return hilbert_to_tile(id - offset, z);
+        }
+
+        offset += count;
+        z += 1;
+    }
 }
 
+// Number of tiles in all zoom levels below z, up to the end of MAX_ZOOM at MAX_ZOOM + 1
+fn pmtiles_zoom_offset(z: u8) -> u64 {
+    assert!(z <= MAX_ZOOM + 1, "zoom {} is above MAX_ZOOM", z);
 
-/**
- * Get the latitudinal value for a given tile corner
- */
-pub fn tile_to_lat(y: i32, z: u8) -> f64 {
-    let n: f64 = PI - 2.0 * PI * y as f64 / (2.0 as f64).powi(z as i32);
-    R2D * (0.5 * (n.exp() - (-n).exp())).atan()
+    ((1 << (2 * z as u64)) - 1) / 3
 }
 
 /**
- *  * Get the tile for a point at a specified zoom level
+ * Get the sorted PMTiles v3 tile IDs of the tiles covering a geometry
  */
-pub fn point_to_tile(lon: f64, lat: f64, z: u8) -> (i32, i32, u8) {
-    let tile_frac = point_to_tile_fraction(lon, lat, z);
+pub fn pmtiles_ids(geom: &Geometry<f64>, zoom: u8) -> Result<Vec<u64>, Error> {
+    let mut ids: Vec<u64> = tiles(geom, zoom)?.into_iter().map(tile_to_pmtiles_id).collect();
 
-    (tile_frac.0.floor() as i32, tile_frac.1.floor() as i32, tile_frac.2)
+    ids.sort_unstable();
+
+    Ok(ids)
 }
 
 /**
- *  * Get the precise fractional tile location for a point at a zoom level
+ * Group a cover into runs of consecutive PMTiles tile IDs, as stored in the
+ * run-length encoded entries of a PMTiles directory
+ *
+ * Returned in the format [ (Tile ID, Run Length), ... ]
  */
-pub fn point_to_tile_fraction(lon: f64, lat: f64, z: u8) -> (f64, f64, u8) {
-    let sin = (lat * D2R).sin();
-    let base: f64 = 2.0;
+pub fn pmtiles_runs(cover: &[(i32, i32, u8)]) -> Vec<(u64, u32)> {
+    let mut ids: Vec<u64> = cover.iter().map(|tile| tile_to_pmtiles_id(*tile)).collect();
 
-    let z2: f64 = base.powf(z as f64);
-    let mut x = z2 * (lon / 360.0 + 0.5);
-    let y = z2 * (0.5 - 0.25 * ((1.0 + sin) / (1.0 - sin)).ln() / PI);
+    ids.sort_unstable();
+    ids.dedup();
 
-    // Wrap Tile X
-    x = x % z2;
-    if x < 0.0 {
-        x = x + z2
+    let mut runs: Vec<(u64, u32)> = Vec::new();
+    for id in ids {
+        match runs.last_mut() {
+            Some(run) if run.0 + run.1 as u64 == id && run.1 < u32::MAX => run.1 += 1,
+            _ => runs.push((id, 1))
+        }
     }
 
-    (x, y, z)
+    runs
 }
 
 #[cfg(test)]
@@ -1266,4 +2590,674 @@ mod tests {
         assert_eq!(point_to_tile(-175.0, -95.0, 2), (0, 3, 2));
         assert_eq!(point_to_tile(-175.0, 95.0, 2), (0, 0, 2));
     }
+
+    #[test]
+    fn test_tile_to_id() {
+        assert_eq!(tile_to_id((0, 0, 0)), 0);
+        assert_eq!(tile_to_id((1, 0, 1)), (1 << 58) | 1);
+        assert_eq!(tile_to_id((0, 1, 1)), (1 << 58) | 2);
+        assert_eq!(tile_to_id((3, 5, 3)), (3 << 58) | 0b100111);
+
+        for tile in [(0, 0, 0), (5, 10, 10), (4579, 6271, 14), (536870911, 0, 29), (0, 536870911, 29)] {
+            assert_eq!(id_to_tile(tile_to_id(tile)), tile);
+        }
+    }
+
+    #[test]
+    fn test_id_range() {
+        let tile = (5, 10, 10);
+        assert_eq!(id_range(tile, 10), (tile_to_id(tile), tile_to_id(tile)));
+
+        let (first, last) = id_range(tile, 12);
+        assert_eq!(last - first, 15);
+
+        for child in get_children(tile) {
+            for grandchild in get_children(child) {
+                let id = tile_to_id(grandchild);
+                assert!(id >= first && id <= last);
+            }
+        }
+
+        assert!(tile_to_id((24, 40, 12)) > last);
+        assert!(tile_to_id((19, 43, 12)) < first);
+    }
+
+    #[test]
+    fn test_tile_ids() {
+        let poly = Polygon::new(
+            LineString(vec![
+                Coordinate { x: 5.11962890625, y: 20.46818922264095 },
+                Coordinate { x: 5.11962890625, y: 20.7663868125152 },
+                Coordinate { x: 5.504150390625, y: 20.7663868125152 },
+                Coordinate { x: 5.504150390625, y: 20.46818922264095 },
+                Coordinate { x: 5.11962890625, y: 20.46818922264095 }
+            ]),
+            Vec::<LineString<f64>>::new()
+        );
+
+        let geom = poly.into();
+        assert_eq!(tile_ids(&geom, 8).unwrap(), vec![
+            tile_to_id((131, 112, 8)),
+            tile_to_id((131, 113, 8))
+        ]);
+    }
+
+    #[test]
+    fn test_tile_to_hilbert() {
+        assert_eq!(tile_to_hilbert((0, 0, 0)), 0);
+        assert_eq!(tile_to_hilbert((0, 0, 1)), 0);
+        assert_eq!(tile_to_hilbert((0, 1, 1)), 1);
+        assert_eq!(tile_to_hilbert((1, 1, 1)), 2);
+        assert_eq!(tile_to_hilbert((1, 0, 1)), 3);
+
+        for d in 0..256 {
+            assert_eq!(tile_to_hilbert(hilbert_to_tile(d, 4)), d);
+        }
+    }
+
+    #[test]
+    fn test_tile_to_pmtiles_id() {
+        assert_eq!(tile_to_pmtiles_id((0, 0, 0)), 0);
+        assert_eq!(tile_to_pmtiles_id((0, 0, 1)), 1);
+        assert_eq!(tile_to_pmtiles_id((0, 1, 1)), 2);
+        assert_eq!(tile_to_pmtiles_id((1, 1, 1)), 3);
+        assert_eq!(tile_to_pmtiles_id((1, 0, 1)), 4);
+        assert_eq!(tile_to_pmtiles_id((0, 0, 2)), 5);
+        assert_eq!(tile_to_pmtiles_id((3423, 1763, 12)), 19078479);
+
+        for tile in [(0, 0, 0), (1, 0, 1), (3423, 1763, 12), (4579, 6271, 14), (1073741823, 0, 30)] {
+            assert_eq!(pmtiles_id_to_tile(tile_to_pmtiles_id(tile)), tile);
+        }
+    }
+
+    #[test]
+    fn test_tiles_ordered() {
+        let line = LineString(vec![
+            Coordinate { x: -106.21719360351562, y: 28.592359801121567 },
+            Coordinate { x: -106.1004638671875, y: 28.791130513231813 },
+            Coordinate { x: -105.87661743164062, y: 28.864519767126602 },
+            Coordinate { x: -105.82374572753905, y: 28.60743139267596 }
+        ]);
+
+        let geom = line.into();
+        let lexicographic = tiles(&geom, 12).unwrap();
+        assert_eq!(tiles_ordered(&geom, 12, Order::Lexicographic).unwrap(), lexicographic);
+
+        let hilbert = tiles_ordered(&geom, 12, Order::Hilbert).unwrap();
+        assert_eq!(hilbert.len(), lexicographic.len());
+        assert!(hilbert.windows(2).all(|w| tile_to_hilbert(w[0]) < tile_to_hilbert(w[1])));
+
+        let morton = tiles_ordered(&geom, 12, Order::Morton).unwrap();
+        assert!(morton.windows(2).all(|w| tile_to_id(w[0]) < tile_to_id(w[1])));
+    }
+
+    #[test]
+    fn test_multipolygon() {
+        let mut polygons: Vec<Polygon<f64>> = Vec::new();
+        let mut expected: Vec<(i32, i32, u8)> = Vec::new();
+
+        for i in 0..50 {
+            let lon = -120.0 + i as f64 * 0.7;
+            let lat = 30.0 + (i % 7) as f64 * 0.9;
+
+            let poly = Polygon::new(
+                LineString(vec![
+                    Coordinate { x: lon, y: lat },
+                    Coordinate { x: lon + 1.1, y: lat },
+                    Coordinate { x: lon + 1.1, y: lat + 0.6 },
+                    Coordinate { x: lon, y: lat + 0.6 },
+                    Coordinate { x: lon, y: lat }
+                ]),
+                Vec::<LineString<f64>>::new()
+            );
+
+            expected.extend(tiles(&poly.clone().into(), 9).unwrap());
+            polygons.push(poly);
+        }
+
+        expected.sort();
+        expected.dedup();
+
+        let geom = MultiPolygon(polygons).into();
+        assert_eq!(tiles(&geom, 9).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_batch_tiles() {
+        let line: Geometry<f64> = LineString(vec![
+            Coordinate { x: -79.37619924545288, y: 38.8345346107744 },
+            Coordinate { x: -79.37211155891418, y: 38.83878001066818 }
+        ]).into();
+        let point: Geometry<f64> = Point::new(-79.37969952821732, 38.8328422301817).into();
+        let other: Geometry<f64> = Point::new(0.0, 0.0).into();
+
+        let index = batch_tiles(vec![(1, &line), (2, &point), (3, &other)], 14).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index[&(4579, 6271, 14)], vec![1, 2]);
+        assert_eq!(index[&(8192, 8192, 14)], vec![3]);
+
+        let collection = Geometry::GeometryCollection(GeometryCollection(vec![other.clone()]));
+        assert_eq!(batch_tiles(vec![("a", other), ("b", collection)], 14), Err(Error::GeomTypeNotSupported));
+    }
+
+    #[test]
+    fn test_point_counts() {
+        let points: MultiPoint<f64> = vec![
+            ( -84.48486328124999, 43.40504748787035 ),
+            ( -90.87890625, 39.90973623453719 ),
+            ( -84.55078125, 43.45291889355468 ),
+            ( -90.8349609375, 39.93711893299021 ),
+            ( -84.5, 43.42 )
+        ].into();
+
+        let counts = point_counts(&points, 4);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&(4, 5, 4)], 3);
+        assert_eq!(counts[&(3, 6, 4)], 2);
+
+        let weighted = weighted_point_counts(points.0.iter().map(|point| (*point, 0.5)), 4);
+        assert_eq!(weighted[&(4, 5, 4)], 1.5);
+        assert_eq!(weighted[&(3, 6, 4)], 1.0);
+    }
+
+    #[test]
+    fn test_count_pyramid() {
+        let mut counts: HashMap<(i32, i32, u8), u64> = HashMap::new();
+        counts.insert((4, 5, 4), 3);
+        counts.insert((3, 6, 4), 2);
+        counts.insert((0, 0, 2), 7);
+
+        let pyramid = count_pyramid(&counts, 2);
+        assert_eq!(pyramid.len(), 7);
+        assert_eq!(pyramid[&(4, 5, 4)], 3);
+        assert_eq!(pyramid[&(2, 2, 3)], 3);
+        assert_eq!(pyramid[&(1, 3, 3)], 2);
+        assert_eq!(pyramid[&(1, 1, 2)], 3);
+        assert_eq!(pyramid[&(0, 1, 2)], 2);
+        assert_eq!(pyramid[&(0, 0, 2)], 7);
+        assert!(!pyramid.contains_key(&(0, 0, 1)));
+
+        let pyramid = count_pyramid(&counts, 0);
+        assert_eq!(pyramid[&(0, 0, 1)], 12);
+        assert_eq!(pyramid[&(0, 0, 0)], 12);
+    }
+
+    #[test]
+    fn test_point_to_tile_pixel() {
+        assert_eq!(point_to_tile_pixel(0.0, 0.0, (512, 512, 10), 4096), (0, 0));
+        assert_eq!(point_to_tile_pixel(0.0, 0.0, (511, 511, 10), 4096), (4096, 4096));
+        assert_eq!(point_to_tile_pixel(-95.93965530395508, 41.26000108568697, (119, 191, 9), 4096), (2263, 1930));
+        assert_eq!(point_to_tile_pixel(180.0, 0.0, (1, 0, 1), 256), (256, 256));
+
+        let (lon, lat) = tile_pixel_to_point(2048.0, 2048.0, (0, 0, 0), 4096);
+        assert_eq!((lon, lat), (0.0, 0.0));
+
+        let (lon, lat) = tile_pixel_to_point(0.0, 0.0, (5, 10, 10), 4096);
+        assert_eq!((lon, lat), (tile_to_lon(5, 10), tile_to_lat(10, 10)));
+    }
+
+    #[test]
+    fn test_geom_to_tile_pixels() {
+        let line: Geometry<f64> = LineString(vec![
+            Coordinate { x: -90.0, y: 0.0 },
+            Coordinate { x: 0.0, y: 0.0 },
+            Coordinate { x: 90.0, y: 66.51326044311186 }
+        ]).into();
+
+        let pixels = geom_to_tile_pixels(&line, (0, 0, 0), 4096);
+        assert_eq!(pixels, LineString(vec![
+            Coordinate { x: 1024, y: 2048 },
+            Coordinate { x: 2048, y: 2048 },
+            Coordinate { x: 3072, y: 1024 }
+        ]).into());
+
+        match tile_pixels_to_geom(&pixels, (0, 0, 0), 4096) {
+            Geometry::LineString(ref back) => {
+                for (a, b) in back.0.iter().zip(vec![(-90.0, 0.0), (0.0, 0.0), (90.0, 66.51326044311186)]) {
+                    assert!((a.x - b.0).abs() < 1e-9 && (a.y - b.1).abs() < 1e-9);
+                }
+            },
+            _ => panic!("expected a linestring")
+        }
+    }
+
+    #[test]
+    fn test_point_to_tile_poles() {
+        assert_eq!(point_to_tile(0.0, 90.0, 2), (2, 0, 2));
+        assert_eq!(point_to_tile(0.0, -90.0, 2), (2, 3, 2));
+        assert_eq!(point_to_tile(0.0, MAX_LAT, 10), (512, 0, 10));
+        assert_eq!(point_to_tile(0.0, -MAX_LAT, 10), (512, 1023, 10));
+        assert_eq!(point_to_tile_fraction(0.0, 90.0, 0), (0.5, 0.0, 0));
+    }
+
+    #[test]
+    fn test_polygon_antarctica() {
+        let poly = Polygon::new(
+            LineString(vec![
+                Coordinate { x: -180.0, y: -60.0 },
+                Coordinate { x: -180.0, y: -90.0 },
+                Coordinate { x: 180.0, y: -90.0 },
+                Coordinate { x: 180.0, y: -60.0 },
+                Coordinate { x: -180.0, y: -60.0 }
+            ]),
+            Vec::<LineString<f64>>::new()
+        );
+
+        let geom = poly.into();
+        assert_eq!(tiles(&geom, 2).unwrap(), vec![
+            (0, 2, 2), (0, 3, 2),
+            (1, 2, 2), (1, 3, 2),
+            (2, 2, 2), (2, 3, 2),
+            (3, 2, 2), (3, 3, 2)
+        ]);
+
+        let cover = tiles(&geom, 6).unwrap();
+        assert_eq!(cover.len(), 64 * 19);
+        assert!(cover.iter().all(|tile| tile.1 >= 45 && tile.1 < 64));
+    }
+
+    #[test]
+    fn test_polygon_arctic() {
+        let poly = Polygon::new(
+            LineString(vec![
+                Coordinate { x: -170.0, y: 88.0 },
+                Coordinate { x: 170.0, y: 88.0 },
+                Coordinate { x: 170.0, y: 90.0 },
+                Coordinate { x: -170.0, y: 90.0 },
+                Coordinate { x: -170.0, y: 88.0 }
+            ]),
+            Vec::<LineString<f64>>::new()
+        );
+
+        let geom = poly.into();
+        assert_eq!(tiles(&geom, 2).unwrap(), vec![(0, 0, 2), (1, 0, 2), (2, 0, 2), (3, 0, 2)]);
+    }
+
+    #[test]
+    fn test_tile_to_metatile() {
+        assert_eq!(tile_to_metatile((4579, 6271, 14), 8), (4576, 6264, 14));
+        assert_eq!(tile_to_metatile((4576, 6264, 14), 8), (4576, 6264, 14));
+        assert_eq!(tile_to_metatile((3, 2, 2), 8), (0, 0, 2));
+        assert_eq!(tile_to_metatile((5, 7, 3), 1), (5, 7, 3));
+    }
+
+    #[test]
+    fn test_metatile_tiles() {
+        assert_eq!(metatile_tiles((0, 0, 0), 8), vec![(0, 0, 0)]);
+        assert_eq!(metatile_tiles((0, 0, 1), 8), vec![(0, 0, 1), (0, 1, 1), (1, 0, 1), (1, 1, 1)]);
+        assert_eq!(metatile_tiles((4576, 6264, 14), 8).len(), 64);
+        assert_eq!(metatile_tiles((8, 8, 4), 8).len(), 64);
+        assert_eq!(metatile_tiles((6, 4, 3), 3), vec![(6, 4, 3), (6, 5, 3), (6, 6, 3), (7, 4, 3), (7, 5, 3), (7, 6, 3)]);
+    }
+
+    #[test]
+    fn test_tiles_metatiled() {
+        let points: MultiPoint<f64> = vec![
+            ( -84.48486328124999, 43.40504748787035 ),
+            ( -90.87890625, 39.90973623453719 )
+        ].into();
+
+        let geom = points.into();
+        assert_eq!(metatiles(&tiles(&geom, 4).unwrap(), 4), vec![(0, 4, 4), (4, 4, 4)]);
+
+        let tiles = tiles_metatiled(&geom, 4, 4).unwrap();
+        assert_eq!(tiles.len(), 32);
+        assert_eq!(tiles[0], (0, 4, 4));
+        assert_eq!(tiles[31], (7, 7, 4));
+
+        assert_eq!(tiles_metatiled(&geom, 1, 8).unwrap(), vec![(0, 0, 1), (0, 1, 1), (1, 0, 1), (1, 1, 1)]);
+    }
+
+    #[test]
+    fn test_tile_to_quadkey() {
+        assert_eq!(tile_to_quadkey((0, 0, 0)), "");
+        assert_eq!(tile_to_quadkey((3, 5, 3)), "213");
+        assert_eq!(tile_to_quadkey((11, 3, 8)), "00001033");
+
+        assert_eq!(quadkey_to_tile(""), Some((0, 0, 0)));
+        assert_eq!(quadkey_to_tile("213"), Some((3, 5, 3)));
+        assert_eq!(quadkey_to_tile("00001033"), Some((11, 3, 8)));
+        assert_eq!(quadkey_to_tile("0124"), None);
+
+        assert_eq!(quadkey_to_tile(&"1".repeat(30)), Some((1073741823, 0, 30)));
+        assert_eq!(quadkey_to_tile(&"1".repeat(31)), None);
+        assert_eq!(quadkey_to_tile(&"1".repeat(300)), None);
+        assert_eq!(tile_to_quadkey((1073741823, 0, 30)), "1".repeat(30));
+    }
+
+    #[test]
+    #[should_panic(expected = "zoom 32 is above MAX_ZOOM")]
+    fn test_quadkey_zoom_limit() {
+        tile_to_quadkey((0, 0, 32));
+    }
+
+    #[test]
+    fn test_diff_tiles() {
+        let cover = vec![(0, 0, 1), (1, 0, 1), (1, 1, 1)];
+        let existing = vec![(1, 1, 1), (0, 1, 1), (0, 0, 0), (1, 0, 1), (1, 1, 1)];
+
+        assert_eq!(diff_tiles(&cover, &existing), TileDiff {
+            missing: vec![(0, 0, 1)],
+            extraneous: vec![(0, 1, 1)]
+        });
+        assert_eq!(diff_tiles(&cover, &cover), TileDiff::default());
+    }
+
+    #[test]
+    fn test_pmtiles_runs() {
+        assert_eq!(pmtiles_runs(&[]), vec![]);
+        assert_eq!(pmtiles_runs(&[(1, 0, 1), (0, 0, 1), (0, 1, 1), (0, 0, 0), (0, 0, 2)]), vec![(0, 3), (4, 2)]);
+        assert_eq!(pmtiles_runs(&[(0, 0, 1), (0, 0, 1)]), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn test_pmtiles_ids() {
+        let geom = Point::new(-77.15664982795715, 38.87419791355846).into();
+        assert_eq!(pmtiles_ids(&geom, 4).unwrap(), vec![tile_to_pmtiles_id((4, 6, 4))]);
+    }
+
+    #[test]
+    fn test_mercator_to_lon_lat() {
+        assert_eq!(mercator_to_lon_lat(0.0, 0.0), (0.0, 0.0));
+
+        let (lon, lat) = mercator_to_lon_lat(-8575605.398444, 4707174.018280);
+        assert!((lon - -77.035974).abs() < 1e-6);
+        assert!((lat - 38.898717).abs() < 1e-6);
+
+        let (x, y) = lon_lat_to_mercator(lon, lat);
+        assert!((x - -8575605.398444).abs() < 1e-6);
+        assert!((y - 4707174.018280).abs() < 1e-6);
+
+        let (_, lat) = mercator_to_lon_lat(0.0, 20037508.342789244);
+        assert!((lat - MAX_LAT).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(is_valid((0, 0, 0)));
+        assert!(is_valid((16383, 16383, 14)));
+        assert!(is_valid(((1 << 30) - 1, 0, MAX_ZOOM)));
+
+        assert!(!is_valid((1, 0, 0)));
+        assert!(!is_valid((-1, 0, 14)));
+        assert!(!is_valid((0, 16384, 14)));
+        assert!(!is_valid((0, 0, MAX_ZOOM + 1)));
+    }
+
+    #[test]
+    fn test_parent() {
+        assert_eq!(parent((5, 10, 10)), Some((2, 5, 9)));
+        assert_eq!(parent((1, 1, 1)), Some((0, 0, 0)));
+        assert_eq!(parent((0, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_max_zoom() {
+        let point: Geometry<f64> = Point::new(-77.15664982795715, 38.87419791355846).into();
+        let line: Geometry<f64> = LineString::from(vec![(-77.0, 38.0), (-77.0001, 38.0001)]).into();
+
+        assert_eq!(tiles(&point, 40), Err(Error::ZoomTooLarge(40)));
+        assert_eq!(tiles(&line, MAX_ZOOM + 1), Err(Error::ZoomTooLarge(MAX_ZOOM + 1)));
+
+        assert!(tiles(&point, MAX_ZOOM).unwrap().iter().all(|tile| is_valid(*tile)));
+        assert!(tiles(&line, MAX_ZOOM).unwrap().iter().all(|tile| is_valid(*tile)));
+    }
+
+    #[test]
+    fn test_ancestors() {
+        assert_eq!(ancestors((5, 10, 4)), vec![(2, 5, 3), (1, 2, 2), (0, 1, 1), (0, 0, 0)]);
+        assert_eq!(ancestors((0, 0, 0)), vec![]);
+    }
+
+    #[test]
+    fn test_descendants() {
+        assert_eq!(descendants((1, 0, 1), 2).collect::<Vec<_>>(), vec![(2, 0, 2), (2, 1, 2), (3, 0, 2), (3, 1, 2)]);
+        assert_eq!(descendants((1, 0, 1), 1).collect::<Vec<_>>(), vec![(1, 0, 1)]);
+        assert_eq!(descendants((1, 0, 1), 0).count(), 0);
+        assert_eq!(descendants((0, 0, 0), MAX_ZOOM).range(), (0, 0, 1073741823, 1073741823));
+        assert_eq!(descendants((0, 0, 0), MAX_ZOOM + 1).count(), 0);
+        assert_eq!(descendants((0, 0, 0), 255).count(), 0);
+
+        let mut block = descendants((5, 10, 10), 14);
+        assert_eq!(block.range(), (80, 160, 95, 175));
+        assert_eq!(block.size_hint(), (256, Some(256)));
+        block.next();
+        assert_eq!(block.size_hint(), (255, Some(255)));
+        assert_eq!(block.count(), 255);
+
+        assert!(descendants((5, 10, 10), 14).all(|tile| is_ancestor_of((5, 10, 10), tile)));
+    }
+
+    #[test]
+    fn test_is_ancestor_of() {
+        assert!(is_ancestor_of((0, 0, 0), (5, 10, 10)));
+        assert!(is_ancestor_of((2, 5, 9), (5, 10, 10)));
+        assert!(!is_ancestor_of((5, 10, 10), (5, 10, 10)));
+        assert!(!is_ancestor_of((5, 10, 10), (2, 5, 9)));
+        assert!(!is_ancestor_of((3, 5, 9), (5, 10, 10)));
+    }
+
+    #[test]
+    fn test_common_ancestor() {
+        assert_eq!(common_ancestor(&[]), None);
+        assert_eq!(common_ancestor(&[(5, 10, 10)]), Some((5, 10, 10)));
+        assert_eq!(common_ancestor(&[(4, 10, 10), (5, 11, 10)]), Some((2, 5, 9)));
+        assert_eq!(common_ancestor(&[(5, 10, 10), (2, 5, 9)]), Some((2, 5, 9)));
+        assert_eq!(common_ancestor(&[(0, 0, 1), (1, 1, 1), (3, 3, 4)]), Some((0, 0, 0)));
+        assert_eq!(common_ancestor(&[(80, 160, 14), (95, 175, 14), (20, 41, 12)]), Some((5, 10, 10)));
+        assert_eq!(common_ancestor(&[(80, 160, 14), (95, 175, 14), (18, 41, 12)]), Some((2, 5, 9)));
+    }
+
+    #[test]
+    fn test_tiles_pyramid() {
+        let poly: Geometry<f64> = Polygon::new(LineString::from(vec![
+            (-79.37619924545288, 38.8345346107744),
+            (-79.37211155891418, 38.83878001066818),
+            (-79.36550259590149, 38.83290029083329),
+            (-79.37619924545288, 38.8345346107744)
+        ]), vec![]).into();
+
+        let pyramid = tiles_pyramid(&poly, 0, 16).unwrap();
+        assert_eq!(pyramid, tiles_pyramid_direct(&poly, 0, 16).unwrap());
+        assert_eq!(&pyramid[..3], &[(0, 0, 0), (0, 0, 1), (1, 1, 2)]);
+        assert_eq!(pyramid.iter().filter(|tile| tile.2 == 16).count(), tiles(&poly, 16).unwrap().len());
+
+        let points: Geometry<f64> = MultiPoint::from(vec![(-79.3, 38.8), (10.0, 10.0)]).into();
+        assert_eq!(tiles_pyramid(&points, 1, 2).unwrap(), vec![(0, 0, 1), (1, 0, 1), (1, 1, 2), (2, 1, 2)]);
+
+        assert_eq!(tiles_pyramid(&poly, 5, 4).unwrap(), vec![]);
+        assert_eq!(tiles_pyramid(&poly, 0, MAX_ZOOM + 1), Err(Error::ZoomTooLarge(MAX_ZOOM + 1)));
+    }
+
+    #[test]
+    fn test_estimate_tile_count() {
+        let point: Geometry<f64> = Point::new(-77.15664982795715, 38.87419791355846).into();
+        assert_eq!(estimate_tile_count(&point, 20), Ok(1));
+
+        let points: Geometry<f64> = MultiPoint::from(vec![(-79.3, 38.8), (-79.3, 38.8), (10.0, 10.0)]).into();
+        assert_eq!(estimate_tile_count(&points, 0), Ok(1));
+        assert_eq!(estimate_tile_count(&points, 10), Ok(3));
+
+        let line: Geometry<f64> = LineString::from(vec![
+            (-106.21719360351562, 28.592359801923266),
+            (-106.2017822265625, 28.6032563131806),
+            (-106.17431640625, 28.6032563131806)
+        ]).into();
+        let poly: Geometry<f64> = Polygon::new(LineString::from(vec![
+            (-79.37619924545288, 38.8345346107744),
+            (-79.37211155891418, 38.83878001066818),
+            (-79.36550259590149, 38.83290029083329),
+            (-79.37619924545288, 38.8345346107744)
+        ]), vec![]).into();
+
+        for zoom in [0, 8, 14, 18, 24].iter() {
+            for geom in [&points, &line, &poly].iter() {
+                assert!(estimate_tile_count(geom, *zoom).unwrap() >= tiles(geom, *zoom).unwrap().len() as u64);
+            }
+        }
+
+        assert_eq!(estimate_tile_count(&poly, MAX_ZOOM + 1), Err(Error::ZoomTooLarge(MAX_ZOOM + 1)));
+    }
+
+    #[test]
+    fn test_tiles_limited() {
+        let ring = LineString::from(vec![(-10.0, -10.0), (10.0, -10.0), (10.0, 10.0), (-10.0, 10.0), (-10.0, -10.0)]);
+        let hole = LineString::from(vec![(-9.0, -9.0), (9.0, -9.0), (9.0, 9.0), (-9.0, 9.0), (-9.0, -9.0)]);
+        let frame: Geometry<f64> = Polygon::new(ring.clone(), vec![hole]).into();
+
+        let cover = tiles(&frame, 10).unwrap();
+        assert!((cover.len() as u64) < estimate_tile_count(&frame, 10).unwrap());
+
+        assert_eq!(tiles_limited(&frame, 10, cover.len()).unwrap(), cover);
+        assert_eq!(tiles_limited(&frame, 10, cover.len() - 1), Err(Error::TooManyTiles(cover.len() - 1)));
+
+        // neither the boundary nor the interior are walked
+        let world: Geometry<f64> = Polygon::new(LineString::from(vec![(-170.0, -80.0), (170.0, -80.0), (170.0, 80.0), (-170.0, 80.0), (-170.0, -80.0)]), vec![]).into();
+        assert_eq!(tiles_limited(&world, MAX_ZOOM, 1_000_000), Err(Error::TooManyTiles(1_000_000)));
+        // the boundary is walked but the interior is counted without being filled
+        assert_eq!(tiles_limited(&world, 12, 100_000), Err(Error::TooManyTiles(100_000)));
+
+        let line: Geometry<f64> = MultiLineString(vec![ring.clone(), ring]).into();
+        assert_eq!(tiles_limited(&line, 4, 100).unwrap(), tiles(&line, 4).unwrap());
+        assert_eq!(tiles_limited(&line, 12, 100), Err(Error::TooManyTiles(100)));
+
+        // thousands of islands sharing a few tiles spread across the world, so both estimates are over the limit
+        let islands: Geometry<f64> = MultiPolygon((0..2000).map(|i| {
+            let (x, y) = ((i % 20) as f64 * 15.0 - 150.0, (i % 20) as f64 * 6.0 - 60.0);
+            Polygon::new(LineString::from(vec![(x, y), (x + 0.1, y), (x + 0.1, y + 0.1), (x, y)]), vec![])
+        }).collect()).into();
+        assert!(estimate_tile_count(&islands, 6).unwrap() > 100);
+        assert_eq!(tiles_limited(&islands, 6, 100).unwrap(), tiles(&islands, 6).unwrap());
+    }
+
+    #[test]
+    fn test_max_zoom_for_budget() {
+        let poly: Geometry<f64> = Polygon::new(LineString::from(vec![
+            (-79.37619924545288, 38.8345346107744),
+            (-79.37211155891418, 38.83878001066818),
+            (-79.36550259590149, 38.83290029083329),
+            (-79.37619924545288, 38.8345346107744)
+        ]), vec![]).into();
+
+        let zoom = max_zoom_for_budget(&poly, 50).unwrap().unwrap();
+        assert!(tiles(&poly, zoom).unwrap().len() <= 50);
+        assert!(tiles(&poly, zoom + 1).unwrap().len() > 50);
+
+        assert_eq!(max_zoom_for_budget(&poly, 0), Ok(None));
+        assert_eq!(max_zoom_for_budget(&Point::new(1.0, 1.0).into(), 1), Ok(Some(MAX_ZOOM)));
+    }
+
+    #[test]
+    fn test_zoom_for_resolution() {
+        // 156543.03 m/px at z0 on the equator for 256px tiles
+        assert_eq!(zoom_for_resolution(156_543.033_928_041, 0.0, 256), 0);
+        assert_eq!(zoom_for_resolution(156_543.033_928_041 / 2.0, 0.0, 256), 1);
+        assert_eq!(zoom_for_resolution(1.0, 0.0, 256), 18);
+        assert_eq!(zoom_for_resolution(1.0, 0.0, 512), 17);
+        assert_eq!(zoom_for_resolution(1.0, 60.0, 256), 17);
+
+        assert_eq!(zoom_for_resolution(1e9, 0.0, 256), 0);
+        assert_eq!(zoom_for_resolution(1e-9, 0.0, 256), MAX_ZOOM);
+    }
+
+    #[test]
+    fn test_tile_geometry() {
+        let tile = (5, 10, 10);
+        let (west, south, east, north) = tile_to_bbox(tile);
+
+        let (lon, lat) = tile_center(tile);
+        assert_eq!(lon, (west + east) / 2.0);
+        assert!(lat > (south + north) / 2.0 && lat < north);
+        assert_eq!(point_to_tile(lon, lat, 10), tile);
+        assert_eq!(tile_center((0, 0, 0)), (0.0, 0.0));
+
+        assert_eq!(tile_corners(tile), [(west, north), (east, north), (east, south), (west, south)]);
+
+        assert_eq!(tile_to_polygon(tile).exterior().0, vec![
+            Coordinate { x: west, y: south },
+            Coordinate { x: east, y: south },
+            Coordinate { x: east, y: north },
+            Coordinate { x: west, y: north },
+            Coordinate { x: west, y: south }
+        ]);
+
+        let rect = tile_to_rect(tile);
+        assert_eq!((rect.min(), rect.max()), (Coordinate { x: west, y: south }, Coordinate { x: east, y: north }));
+    }
+
+    #[test]
+    fn test_tile_pixel_sizes() {
+        let tile = (5, 10, 10);
+
+        for size in [256, 512].iter() {
+            let size = *size as f64;
+
+            assert_eq!(tile_pixel_to_point(0.0, 0.0, tile, size as u32), tile_corners(tile)[0]);
+            assert_eq!(tile_pixel_to_point(size, size, tile, size as u32), tile_corners(tile)[2]);
+            assert_eq!(tile_pixel_to_point(size / 2.0, size / 2.0, tile, size as u32), tile_center(tile));
+        }
+
+        assert_eq!(tile_pixel_to_point(128.0, 64.0, tile, 256), tile_pixel_to_point(256.0, 128.0, tile, 512));
+    }
+
+    #[test]
+    fn test_zoom_limits() {
+        let point: Geometry<f64> = Point::new(-77.15664982795715, 38.87419791355846).into();
+
+        assert_eq!(tile_ids(&point, ID_MAX_ZOOM).unwrap().len(), 1);
+        assert_eq!(tile_ids(&point, 30), Err(Error::IdZoomTooLarge(30)));
+        assert_eq!(tile_ids(&point, MAX_ZOOM + 1), Err(Error::IdZoomTooLarge(MAX_ZOOM + 1)));
+
+        let tile = tiles(&point, MAX_ZOOM).unwrap()[0];
+        assert_eq!(pmtiles_id_to_tile(tile_to_pmtiles_id(tile)), tile);
+        assert_eq!(pmtiles_id_to_tile(pmtiles_zoom_offset(MAX_ZOOM + 1) - 1).2, MAX_ZOOM);
+    }
+
+    #[test]
+    #[should_panic(expected = "zoom 31 is above MAX_ZOOM")]
+    fn test_hilbert_zoom_limit() {
+        tile_to_hilbert((0, 0, MAX_ZOOM + 1));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is outside the grid of its zoom")]
+    fn test_tile_to_id_invalid() {
+        tile_to_id((2, 0, 1));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_fill_intersections_bands() {
+        // a donut spanning hundreds of rows, so several uneven bands are filled in parallel
+        let poly = Polygon::new(
+            LineString(vec![
+                Coordinate { x: -60.0, y: -60.0 },
+                Coordinate { x: 60.0, y: -60.0 },
+                Coordinate { x: 15.0, y: 0.0 },
+                Coordinate { x: 60.0, y: 60.0 },
+                Coordinate { x: -60.0, y: 60.0 },
+                Coordinate { x: -60.0, y: -60.0 }
+            ]),
+            vec![LineString(vec![
+                Coordinate { x: -40.0, y: -30.0 },
+                Coordinate { x: -40.0, y: 30.0 },
+                Coordinate { x: -10.0, y: 30.0 },
+                Coordinate { x: -10.0, y: -30.0 },
+                Coordinate { x: -40.0, y: -30.0 }
+            ])]
+        );
+
+        let mut ring_tiles: Vec<(i32, i32, u8)> = Vec::new();
+        let intersections = poly_intersections(&mut ring_tiles, &poly, 10);
+        assert!(intersections.len() > 2 * 512);
+
+        let mut parallel: Vec<(i32, i32, u8)> = Vec::new();
+        let mut serial: Vec<(i32, i32, u8)> = Vec::new();
+        fill_intersections(&mut parallel, &intersections, 10);
+        fill_spans(&mut serial, &intersections, 10);
+
+        assert!(!serial.is_empty());
+        assert_eq!(parallel, serial);
+    }
 }
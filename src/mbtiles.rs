@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+
+use super::{diff_tiles, Error, TileDiff};
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Error {
+        Error::MBTiles(err.to_string())
+    }
+}
+
+/**
+ * Write a cover into an MBTiles database as placeholder tiles
+ *
+ * The database is created with the MBTiles `metadata` & `tiles` schema if it
+ * does not exist. Placeholders have NULL tile data and are written to the
+ * `map` table instead when the database uses the deduplicated `map`/`images`
+ * schema. Tiles already present are left untouched
+ */
+pub fn write_mbtiles<P: AsRef<Path>>(path: P, tiles: &[(i32, i32, u8)]) -> Result<(), Error> {
+    let mut conn = Connection::open(path)?;
+
+    let table = if has_table(&conn, "map")? {
+        "map"
+    } else {
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS metadata (name TEXT, value TEXT);
+            CREATE TABLE IF NOT EXISTS tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+            CREATE UNIQUE INDEX IF NOT EXISTS tile_index ON tiles (zoom_level, tile_column, tile_row);
+        ")?;
+
+        "tiles"
+    };
+
+    let tx = conn.transaction()?;
+    {
+        let mut insert = tx.prepare(&format!("INSERT OR IGNORE INTO {} (zoom_level, tile_column, tile_row) VALUES (?1, ?2, ?3)", table))?;
+
+        for tile in tiles {
+            insert.execute((tile.2, tile.0, flip_y(*tile)))?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+/**
+ * Read the tiles present in an MBTiles database
+ *
+ * Returned sorted, in XYZ rather than the TMS rows MBTiles stores
+ */
+pub fn read_mbtiles<P: AsRef<Path>>(path: P) -> Result<Vec<(i32, i32, u8)>, Error> {
+    // read only, so a missing database is an error rather than created empty
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let table = if has_table(&conn, "map")? { "map" } else { "tiles" };
+
+    let mut select = conn.prepare(&format!("SELECT zoom_level, tile_column, tile_row FROM {}", table))?;
+    let rows = select.query_map([], |row| {
+        let tms: (i32, i32, u8) = (row.get(1)?, row.get(2)?, row.get(0)?);
+        Ok((tms.0, flip_y(tms), tms.2))
+    })?;
+
+    let mut tiles: Vec<(i32, i32, u8)> = Vec::new();
+    for tile in rows {
+        tiles.push(tile?);
+    }
+
+    tiles.sort_unstable();
+
+    Ok(tiles)
+}
+
+/**
+ * Compare a cover against the tiles present in an MBTiles database
+ */
+pub fn mbtiles_diff<P: AsRef<Path>>(path: P, cover: &[(i32, i32, u8)]) -> Result<TileDiff, Error> {
+    Ok(diff_tiles(cover, &read_mbtiles(path)?))
+}
+
+fn has_table(conn: &Connection, name: &str) -> Result<bool, Error> {
+    let found: Option<String> = conn.query_row(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [name],
+        |row| row.get(0)
+    ).optional()?;
+
+    Ok(found.is_some())
+}
+
+// Convert between XYZ and TMS rows, which is its own inverse
+fn flip_y(tile: (i32, i32, u8)) -> i32 {
+    ((1_i64 << tile.2) - 1 - tile.1 as i64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    // unique per process so concurrent test runs don't share databases
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("tilecover-test-{}-{}.mbtiles", name, process::id()))
+    }
+
+    #[test]
+    fn test_mbtiles() {
+        let path = temp_path("mbtiles");
+        let _ = fs::remove_file(&path);
+
+        write_mbtiles(&path, &[(0, 0, 1), (1, 0, 1), (4579, 6271, 14)]).unwrap();
+        write_mbtiles(&path, &[(1, 0, 1)]).unwrap();
+
+        assert_eq!(read_mbtiles(&path).unwrap(), vec![(0, 0, 1), (1, 0, 1), (4579, 6271, 14)]);
+
+        let conn = Connection::open(&path).unwrap();
+        let row: i32 = conn.query_row("SELECT tile_row FROM tiles WHERE zoom_level = 14", [], |row| row.get(0)).unwrap();
+        assert_eq!(row, 10112);
+
+        let diff = mbtiles_diff(&path, &[(0, 0, 1), (0, 1, 1)]).unwrap();
+        assert_eq!(diff.missing, vec![(0, 1, 1)]);
+        assert_eq!(diff.extraneous, vec![(1, 0, 1)]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mbtiles_map_schema() {
+        let path = temp_path("map");
+        let _ = fs::remove_file(&path);
+
+        Connection::open(&path).unwrap().execute_batch("
+            CREATE TABLE map (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_id TEXT);
+            CREATE UNIQUE INDEX map_index ON map (zoom_level, tile_column, tile_row);
+            CREATE TABLE images (tile_data BLOB, tile_id TEXT);
+        ").unwrap();
+
+        write_mbtiles(&path, &[(0, 0, 0)]).unwrap();
+        assert_eq!(read_mbtiles(&path).unwrap(), vec![(0, 0, 0)]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mbtiles_error() {
+        let path = temp_path("missing");
+
+        match read_mbtiles(&path) {
+            Err(Error::MBTiles(_)) => (),
+            other => panic!("expected an MBTiles error, got {:?}", other)
+        }
+        match mbtiles_diff(&path, &[(0, 0, 0)]) {
+            Err(Error::MBTiles(_)) => (),
+            other => panic!("expected an MBTiles error, got {:?}", other)
+        }
+        assert!(!path.exists());
+
+        let path = env::temp_dir().join(format!("tilecover-test-missing-{}", process::id())).join("nope.mbtiles");
+        match write_mbtiles(&path, &[(0, 0, 0)]) {
+            Err(Error::MBTiles(_)) => (),
+            other => panic!("expected an MBTiles error, got {:?}", other)
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::f64::consts::PI;
+
+use super::{tile_center, tile_to_bbox, D2R, EARTH_RADIUS, MAX_LAT};
+
+// OGC standardized rendering pixel size in meters, 0.28mm
+const OGC_PIXEL_SIZE: f64 = 0.00028;
+
+/**
+ * Get the ground resolution in meters per pixel at a latitude and zoom for
+ * tiles of the given size in pixels
+ */
+pub fn ground_resolution(latitude: f64, zoom: u8, tile_size: u32) -> f64 {
+    (latitude.clamp(-MAX_LAT, MAX_LAT) * D2R).cos() * 2.0 * PI * EARTH_RADIUS / (tile_size as f64 * 2.0_f64.powi(zoom as i32))
+}
+
+/**
+ * Get the map scale denominator at a latitude and zoom for tiles of the given
+ * size in pixels, using the OGC standardized 0.28mm pixel
+ */
+pub fn scale_denominator(latitude: f64, zoom: u8, tile_size: u32) -> f64 {
+    ground_resolution(latitude, zoom, tile_size) / OGC_PIXEL_SIZE
+}
+
+/**
+ * Get the ground resolution in meters per pixel at the center of a tile
+ */
+pub fn tile_resolution(tile: (i32, i32, u8), tile_size: u32) -> f64 {
+    ground_resolution(tile_center(tile).1, tile.2, tile_size)
+}
+
+/**
+ * Get the ground width and height of a tile in meters
+ *
+ * The width is measured along the tile's center latitude
+ *
+ * Returned in the format [ Width, Height ]
+ */
+pub fn tile_size_meters(tile: (i32, i32, u8)) -> (f64, f64) {
+    let (_, south, _, north) = tile_to_bbox(tile);
+
+    (
+        ground_resolution(tile_center(tile).1, tile.2, 1),
+        (north - south) * D2R * EARTH_RADIUS
+    )
+}
+
+/**
+ * Get the ground area of a tile in km²
+ */
+pub fn tile_area_km2(tile: (i32, i32, u8)) -> f64 {
+    let (west, south, east, north) = tile_to_bbox(tile);
+
+    EARTH_RADIUS * EARTH_RADIUS * (east - west) * D2R * ((north * D2R).sin() - (south * D2R).sin()) / 1_000_000.0
+}
+
+/**
+ * Get the total ground area of a cover in km²
+ *
+ * Tiles are summed as is, so a cover mixing a tile with its descendants
+ * counts their overlap twice
+ */
+pub fn cover_area_km2(cover: &[(i32, i32, u8)]) -> f64 {
+    cover.iter().map(|tile| tile_area_km2(*tile)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::descendants;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() < tolerance, "{} is not within {} of {}", actual, tolerance, expected);
+    }
+
+    #[test]
+    fn test_ground_resolution() {
+        assert_close(ground_resolution(0.0, 0, 256), 156_543.033_928, 1e-6);
+        assert_close(ground_resolution(0.0, 0, 512), 78_271.516_964, 1e-6);
+        assert_close(ground_resolution(60.0, 1, 256), 39_135.758_482, 1e-6);
+
+        assert_close(scale_denominator(0.0, 0, 256), 559_082_264.029, 1e-3);
+        assert_close(scale_denominator(0.0, 18, 256), 2_132.729_584, 1e-6);
+    }
+
+    #[test]
+    fn test_tile_metrics() {
+        // halfway down a z1 tile in mercator y is 66.51° north
+        assert_close(tile_resolution((0, 0, 1), 256), ground_resolution(66.513_26, 1, 256), 1e-2);
+
+        let (width, height) = tile_size_meters((0, 0, 0));
+        assert_close(width, 2.0 * PI * EARTH_RADIUS, 1e-6);
+        assert_close(height, 2.0 * MAX_LAT * D2R * EARTH_RADIUS, 1e-6);
+
+        // the whole sphere between the mercator latitude limits
+        assert_close(tile_area_km2((0, 0, 0)), 4.0 * PI * EARTH_RADIUS * EARTH_RADIUS * (MAX_LAT * D2R).sin() / 1_000_000.0, 1e-3);
+    }
+
+    #[test]
+    fn test_cover_area_km2() {
+        let children: Vec<(i32, i32, u8)> = descendants((5, 10, 10), 12).collect();
+
+        assert_close(cover_area_km2(&children), tile_area_km2((5, 10, 10)), 1e-9);
+        assert_eq!(cover_area_km2(&[]), 0.0);
+    }
+}
@@ -0,0 +1,485 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use super::{pmtiles_id_to_tile, pmtiles_zoom_offset, tile_to_pmtiles_id, Error, TileDiff};
+
+const HEADER_LENGTH: usize = 127;
+
+// Leaf directories are at most a couple of levels deep in practice; guards against cycles
+const MAX_DIRECTORY_DEPTH: u8 = 8;
+
+/**
+ * Header of a PMTiles v3 archive
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PMTilesHeader {
+    pub root_dir_offset: u64,
+    pub root_dir_length: u64,
+    pub metadata_offset: u64,
+    pub metadata_length: u64,
+    pub leaf_dirs_offset: u64,
+    pub leaf_dirs_length: u64,
+    pub tile_data_offset: u64,
+    pub tile_data_length: u64,
+    pub addressed_tiles: u64,
+    pub tile_entries: u64,
+    pub tile_contents: u64,
+    pub clustered: bool,
+    pub internal_compression: u8,
+    pub tile_compression: u8,
+    pub tile_type: u8,
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    /// Returned in the format [ West, South, East, North ]
+    pub bounds: (f64, f64, f64, f64),
+    pub center_zoom: u8,
+    /// Returned in the format [ Lon, Lat ]
+    pub center: (f64, f64)
+}
+
+/**
+ * Entry of a PMTiles v3 directory
+ *
+ * A run length of 0 points at a leaf directory rather than tile data
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PMTilesEntry {
+    pub tile_id: u64,
+    pub offset: u64,
+    pub length: u32,
+    pub run_length: u32
+}
+
+/**
+ * Read the header of a PMTiles v3 archive
+ */
+pub fn read_pmtiles_header<P: AsRef<Path>>(path: P) -> Result<PMTilesHeader, Error> {
+    let mut file = File::open(path)?;
+
+    read_header(&mut file)
+}
+
+/**
+ * Read every tile entry of a PMTiles v3 archive, following leaf directories
+ *
+ * Returned sorted by tile ID
+ */
+pub fn read_pmtiles_entries<P: AsRef<Path>>(path: P) -> Result<Vec<PMTilesEntry>, Error> {
+    let mut file = File::open(path)?;
+    let header = read_header(&mut file)?;
+
+    let mut entries: Vec<PMTilesEntry> = Vec::new();
+    read_directory(&mut file, &header, header.root_dir_offset, header.root_dir_length, &mut entries, 0)?;
+
+    entries.sort_unstable_by_key(|entry| entry.tile_id);
+
+    Ok(entries)
+}
+
+/**
+ * Compare a cover against the tiles present in a PMTiles v3 archive
+ *
+ * Cover tiles are looked up in the archive's runs, so long runs such as ocean
+ * tiles are only expanded where they hold tiles outside the cover
+ */
+pub fn pmtiles_diff<P: AsRef<Path>>(path: P, cover: &[(i32, i32, u8)]) -> Result<TileDiff, Error> {
+    let entries = read_pmtiles_entries(path)?;
+
+    let mut ids: Vec<u64> = cover.iter().map(|&tile| tile_to_pmtiles_id(tile)).collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    let mut zooms: Vec<u8> = cover.iter().map(|tile| tile.2).collect();
+    zooms.sort_unstable();
+    zooms.dedup();
+
+    let mut diff = TileDiff {
+        missing: ids.iter().filter(|&&id| !runs_contain(&entries, id)).map(|&id| pmtiles_id_to_tile(id)).collect(),
+        extraneous: Vec::new()
+    };
+
+    for entry in entries.iter() {
+        let end = entry.tile_id.saturating_add(entry.run_length as u64);
+
+        // only the parts of each run that fall in a zoom of the cover are compared
+        for &zoom in &zooms {
+            let first = entry.tile_id.max(pmtiles_zoom_offset(zoom));
+            let last = end.min(pmtiles_zoom_offset(zoom + 1));
+            if first >= last {
+                continue;
+            }
+
+            let covered = &ids[ids.partition_point(|&id| id < first)..ids.partition_point(|&id| id < last)];
+            if covered.len() as u64 == last - first {
+                continue;
+            }
+
+            let mut covered = covered.iter().peekable();
+            for id in first..last {
+                if covered.peek() == Some(&&id) {
+                    covered.next();
+                } else {
+                    diff.extraneous.push(pmtiles_id_to_tile(id));
+                }
+            }
+        }
+    }
+
+    diff.missing.sort_unstable();
+    diff.extraneous.sort_unstable();
+    diff.extraneous.dedup();
+
+    Ok(diff)
+}
+
+// Whether a tile ID falls in the run of the last entry starting at or before it
+fn runs_contain(entries: &[PMTilesEntry], id: u64) -> bool {
+    match entries.partition_point(|entry| entry.tile_id <= id) {
+        0 => false,
+        after => id - entries[after - 1].tile_id < entries[after - 1].run_length as u64
+    }
+}
+
+fn read_header<R: Read>(reader: &mut R) -> Result<PMTilesHeader, Error> {
+    let mut buf = [0_u8; HEADER_LENGTH];
+    reader.read_exact(&mut buf)?;
+
+    if &buf[0..7] != b"PMTiles" {
+        return Err(Error::PMTiles(String::from("not a PMTiles archive")));
+    }
+
+    if buf[7] != 3 {
+        return Err(Error::PMTiles(format!("unsupported PMTiles version {}", buf[7])));
+    }
+
+    let u64_at = |i: usize| {
+        let mut bytes = [0_u8; 8];
+        bytes.copy_from_slice(&buf[i..i + 8]);
+        u64::from_le_bytes(bytes)
+    };
+
+    let e7_at = |i: usize| {
+        let mut bytes = [0_u8; 4];
+        bytes.copy_from_slice(&buf[i..i + 4]);
+        i32::from_le_bytes(bytes) as f64 / 10_000_000.0
+    };
+
+    Ok(PMTilesHeader {
+        root_dir_offset: u64_at(8),
+        root_dir_length: u64_at(16),
+        metadata_offset: u64_at(24),
+        metadata_length: u64_at(32),
+        leaf_dirs_offset: u64_at(40),
+        leaf_dirs_length: u64_at(48),
+        tile_data_offset: u64_at(56),
+        tile_data_length: u64_at(64),
+        addressed_tiles: u64_at(72),
+        tile_entries: u64_at(80),
+        tile_contents: u64_at(88),
+        clustered: buf[96] == 1,
+        internal_compression: buf[97],
+        tile_compression: buf[98],
+        tile_type: buf[99],
+        min_zoom: buf[100],
+        max_zoom: buf[101],
+        bounds: (e7_at(102), e7_at(106), e7_at(110), e7_at(114)),
+        center_zoom: buf[118],
+        center: (e7_at(119), e7_at(123))
+    })
+}
+
+fn read_directory(file: &mut File, header: &PMTilesHeader, offset: u64, length: u64, entries: &mut Vec<PMTilesEntry>, depth: u8) -> Result<(), Error> {
+    if depth > MAX_DIRECTORY_DEPTH {
+        return Err(Error::PMTiles(String::from("leaf directories nested too deeply")));
+    }
+
+    // lengths come from the file, so check them before allocating
+    let file_length = file.metadata()?.len();
+    if offset.checked_add(length).is_none_or(|end| end > file_length) {
+        return Err(Error::PMTiles(String::from("directory extends past the end of the archive")));
+    }
+
+    let mut compressed = vec![0_u8; length as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut compressed)?;
+
+    let bytes = match header.internal_compression {
+        1 => compressed,
+        2 => {
+            let mut bytes: Vec<u8> = Vec::new();
+            GzDecoder::new(&compressed[..]).read_to_end(&mut bytes)?;
+            bytes
+        },
+        compression => return Err(Error::PMTiles(format!("unsupported directory compression {}", compression)))
+    };
+
+    for entry in parse_directory(&bytes)? {
+        if entry.run_length == 0 {
+            let offset = match header.leaf_dirs_offset.checked_add(entry.offset) {
+                Some(offset) => offset,
+                None => return Err(Error::PMTiles(String::from("directory extends past the end of the archive")))
+            };
+
+            read_directory(file, header, offset, entry.length as u64, entries, depth + 1)?;
+        } else {
+            entries.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Decode a serialized directory: the entry count followed by columns of
+ * delta encoded tile IDs, run lengths, lengths and offsets
+ */
+fn parse_directory(bytes: &[u8]) -> Result<Vec<PMTilesEntry>, Error> {
+    let mut pos = 0;
+    let count = read_varint(bytes, &mut pos)? as usize;
+
+    // every entry takes at least four bytes
+    if count > bytes.len() / 4 {
+        return Err(Error::PMTiles(String::from("directory entry count exceeds its length")));
+    }
+
+    let mut entries = vec![PMTilesEntry::default(); count];
+
+    let mut tile_id: u64 = 0;
+    for entry in entries.iter_mut() {
+        tile_id = match tile_id.checked_add(read_varint(bytes, &mut pos)?) {
+            Some(tile_id) => tile_id,
+            None => return Err(Error::PMTiles(String::from("directory tile ID overflows")))
+        };
+        entry.tile_id = tile_id;
+    }
+
+    for entry in entries.iter_mut() {
+        entry.run_length = read_varint(bytes, &mut pos)? as u32;
+    }
+
+    for entry in entries.iter_mut() {
+        entry.length = read_varint(bytes, &mut pos)? as u32;
+    }
+
+    for i in 0..count {
+        let value = read_varint(bytes, &mut pos)?;
+
+        // 0 means the entry directly follows the previous one
+        entries[i].offset = if value == 0 && i > 0 {
+            entries[i - 1].offset.saturating_add(entries[i - 1].length as u64)
+        } else {
+            value.saturating_sub(1)
+        };
+    }
+
+    Ok(entries)
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64, Error> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = match bytes.get(*pos) {
+            Some(byte) => *byte,
+            None => return Err(Error::PMTiles(String::from("directory ends mid varint")))
+        };
+        *pos += 1;
+
+        if shift >= 64 {
+            return Err(Error::PMTiles(String::from("varint overflows 64 bits")));
+        }
+
+        value |= ((byte & 0x7f) as u64) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::process;
+
+    fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            buf.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        buf.push(value as u8);
+    }
+
+    fn encode_directory(entries: &[PMTilesEntry]) -> Vec<u8> {
+        let mut buf: Vec<u8> = Vec::new();
+        write_varint(&mut buf, entries.len() as u64);
+
+        let mut last_id = 0;
+        for entry in entries {
+            write_varint(&mut buf, entry.tile_id - last_id);
+            last_id = entry.tile_id;
+        }
+        for entry in entries {
+            write_varint(&mut buf, entry.run_length as u64);
+        }
+        for entry in entries {
+            write_varint(&mut buf, entry.length as u64);
+        }
+        for (i, entry) in entries.iter().enumerate() {
+            if i > 0 && entry.offset == entries[i - 1].offset + entries[i - 1].length as u64 {
+                write_varint(&mut buf, 0);
+            } else {
+                write_varint(&mut buf, entry.offset + 1);
+            }
+        }
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&buf).unwrap();
+        gzip.finish().unwrap()
+    }
+
+    fn entry(tile_id: u64, offset: u64, length: u32, run_length: u32) -> PMTilesEntry {
+        PMTilesEntry { tile_id, offset, length, run_length }
+    }
+
+    // unique per process so concurrent test runs don't share archives
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("tilecover-test-{}-{}.pmtiles", name, process::id()))
+    }
+
+    // Archive with tiles 0-4 in the root directory and a leaf holding z2 tiles 5, 6 & 10
+    fn write_archive(name: &str) -> PathBuf {
+        let leaf = encode_directory(&[
+            entry(5, 50, 10, 2),
+            entry(10, 60, 10, 1)
+        ]);
+        let root = encode_directory(&[
+            entry(0, 0, 10, 1),
+            entry(1, 10, 10, 4),
+            entry(5, 0, leaf.len() as u32, 0)
+        ]);
+
+        let mut header = vec![0_u8; HEADER_LENGTH];
+        header[0..7].copy_from_slice(b"PMTiles");
+        header[7] = 3;
+        header[8..16].copy_from_slice(&(HEADER_LENGTH as u64).to_le_bytes());
+        header[16..24].copy_from_slice(&(root.len() as u64).to_le_bytes());
+        header[40..48].copy_from_slice(&((HEADER_LENGTH + root.len()) as u64).to_le_bytes());
+        header[48..56].copy_from_slice(&(leaf.len() as u64).to_le_bytes());
+        header[72..80].copy_from_slice(&8_u64.to_le_bytes());
+        header[96] = 1;
+        header[97] = 2;
+        header[101] = 2;
+        header[102..106].copy_from_slice(&(-1_800_000_000_i32).to_le_bytes());
+        header[110..114].copy_from_slice(&1_800_000_000_i32.to_le_bytes());
+        header[123..127].copy_from_slice(&123_456_789_i32.to_le_bytes());
+
+        let path = temp_path(name);
+        let mut file = File::create(&path).unwrap();
+        file.write_all(&header).unwrap();
+        file.write_all(&root).unwrap();
+        file.write_all(&leaf).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn test_read_pmtiles_header() {
+        let path = write_archive("header");
+
+        let header = read_pmtiles_header(&path).unwrap();
+        assert_eq!(header.root_dir_offset, 127);
+        assert_eq!(header.addressed_tiles, 8);
+        assert!(header.clustered);
+        assert_eq!(header.internal_compression, 2);
+        assert_eq!(header.max_zoom, 2);
+        assert_eq!(header.bounds, (-180.0, 0.0, 180.0, 0.0));
+        assert_eq!(header.center, (0.0, 12.3456789));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_pmtiles_entries() {
+        let path = write_archive("entries");
+
+        assert_eq!(read_pmtiles_entries(&path).unwrap(), vec![
+            entry(0, 0, 10, 1),
+            entry(1, 10, 10, 4),
+            entry(5, 50, 10, 2),
+            entry(10, 60, 10, 1)
+        ]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pmtiles_diff() {
+        let path = write_archive("diff");
+
+        let cover = vec![pmtiles_id_to_tile(5), pmtiles_id_to_tile(10), pmtiles_id_to_tile(20)];
+        assert_eq!(tile_to_pmtiles_id(cover[0]), 5);
+
+        assert_eq!(pmtiles_diff(&path, &cover).unwrap(), TileDiff {
+            missing: vec![pmtiles_id_to_tile(20)],
+            extraneous: vec![pmtiles_id_to_tile(6)]
+        });
+
+        let diff = pmtiles_diff(&path, &[(0, 0, 1)]).unwrap();
+        assert_eq!(diff.missing, vec![]);
+        assert_eq!(diff.extraneous.len(), 3);
+
+        // the z1 run is wholly inside this cover
+        let diff = pmtiles_diff(&path, &[(0, 0, 1), (0, 1, 1), (1, 0, 1), (1, 1, 1), (0, 0, 0)]).unwrap();
+        assert_eq!(diff, TileDiff::default());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pmtiles_invalid() {
+        let path = temp_path("invalid");
+        fs::write(&path, vec![0_u8; HEADER_LENGTH]).unwrap();
+
+        assert_eq!(read_pmtiles_header(&path), Err(Error::PMTiles(String::from("not a PMTiles archive"))));
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(parse_directory(&[2, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01, 1, 0, 0, 0, 0, 0]), Err(Error::PMTiles(String::from("directory tile ID overflows"))));
+        assert_eq!(parse_directory(&[2, 0x80]), Err(Error::PMTiles(String::from("directory entry count exceeds its length"))));
+        assert_eq!(parse_directory(&[1, 1, 1, 0x80]), Err(Error::PMTiles(String::from("directory ends mid varint"))));
+    }
+
+    #[test]
+    fn test_pmtiles_directory_bounds() {
+        let path = write_archive("bounds");
+
+        // root directory claiming to be far larger than the archive
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[16..24].copy_from_slice(&(1_u64 << 62).to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(read_pmtiles_entries(&path), Err(Error::PMTiles(String::from("directory extends past the end of the archive"))));
+
+        // root directory offset that overflows once the length is added
+        bytes[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        bytes[16..24].copy_from_slice(&1_u64.to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(read_pmtiles_entries(&path), Err(Error::PMTiles(String::from("directory extends past the end of the archive"))));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,404 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::convert::TryInto;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use geo::Geometry;
+use geojson::{GeoJson, Value};
+
+use super::{sort_dedup, tiles, Error};
+
+// Bytes of a tile in a spilled run: x & y as little endian i32 then z
+const RUN_TILE_SIZE: usize = 9;
+
+// Most runs open at once while merging, well below common file descriptor limits
+const MERGE_FAN_IN: usize = 64;
+
+static RUN_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Next tile of a run along with the index of the run it came from
+type RunHead = Reverse<((i32, i32, u8), usize)>;
+
+impl From<geojson::Error> for Error {
+    fn from(err: geojson::Error) -> Error {
+        Error::GeoJson(err.to_string())
+    }
+}
+
+/**
+ * Cover each feature of a newline-delimited GeoJSON stream, writing its tiles
+ * to `writer` as `z/x/y` lines
+ *
+ * Lines may hold a Feature, a Geometry or a FeatureCollection and may be
+ * prefixed by the RFC 8142 record separator. Features without a geometry and
+ * blank lines are skipped and GeometryCollections are covered member by member.
+ * Tiles are written per feature, so a tile shared by several features is
+ * written once for each of them. Empty geometries cover no tiles, while a line
+ * that fails to parse or cover aborts the stream with an error naming it
+ *
+ * Returns the number of tiles written
+ */
+pub fn stream_tiles<R: BufRead, W: Write>(reader: R, writer: W, zoom: u8) -> Result<u64, Error> {
+    let mut writer = BufWriter::new(writer);
+    let mut written: u64 = 0;
+
+    for_each_cover(reader, zoom, |cover| {
+        for tile in cover {
+            write_tile(&mut writer, tile)?;
+            written += 1;
+        }
+
+        Ok(())
+    })?;
+
+    writer.flush()?;
+
+    Ok(written)
+}
+
+/**
+ * Cover a newline-delimited GeoJSON stream like `stream_tiles`, writing each
+ * tile once in sorted order
+ *
+ * At most `buffer_tiles` tiles are held in memory; beyond that sorted runs are
+ * spilled to the system temp directory and merged once the stream ends, in
+ * passes when there are too many runs to open at once
+ *
+ * Returns the number of tiles written
+ */
+pub fn stream_tiles_dedup<R: BufRead, W: Write>(reader: R, writer: W, zoom: u8, buffer_tiles: usize) -> Result<u64, Error> {
+    let buffer_tiles = buffer_tiles.max(1);
+
+    let mut buffer: Vec<(i32, i32, u8)> = Vec::new();
+    let mut runs = Runs { paths: Vec::new() };
+
+    for_each_cover(reader, zoom, |cover| {
+        buffer.extend(cover);
+
+        if buffer.len() >= buffer_tiles {
+            sort_dedup(&mut buffer);
+
+            // dedup may have freed enough room to keep going in memory
+            if buffer.len() * 2 > buffer_tiles {
+                runs.spill(&buffer)?;
+                buffer.clear();
+            }
+        }
+
+        Ok(())
+    })?;
+
+    sort_dedup(&mut buffer);
+
+    let mut writer = BufWriter::new(writer);
+    let written = runs.merge(buffer, &mut writer)?;
+    writer.flush()?;
+
+    Ok(written)
+}
+
+// Cover each geometry of the stream, naming the line of any parse or cover error
+fn for_each_cover<R, F>(mut reader: R, zoom: u8, mut f: F) -> Result<(), Error>
+    where R: BufRead, F: FnMut(Vec<(i32, i32, u8)>) -> Result<(), Error>
+{
+    let mut line = String::new();
+    let mut number: u64 = 0;
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+
+        let text = line.trim_start_matches('\u{1e}').trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        let geojson = GeoJson::from_str(text).map_err(|err| line_error(number, err.into()))?;
+
+        let values = match geojson {
+            GeoJson::Geometry(geometry) => vec![geometry.value],
+            GeoJson::Feature(feature) => feature.geometry.into_iter().map(|geometry| geometry.value).collect(),
+            GeoJson::FeatureCollection(collection) => {
+                collection.features.into_iter().filter_map(|feature| feature.geometry).map(|geometry| geometry.value).collect()
+            }
+        };
+
+        for value in values {
+            let mut covers: Vec<Vec<(i32, i32, u8)>> = Vec::new();
+            each_member(value, &mut |geom| {
+                covers.push(tiles(&geom, zoom)?);
+
+                Ok(())
+            }).map_err(|err| line_error(number, err))?;
+
+            for cover in covers {
+                f(cover)?;
+            }
+        }
+    }
+}
+
+// Flatten GeometryCollections, which `tiles` doesn't cover, into their members
+fn each_member<F>(value: Value, f: &mut F) -> Result<(), Error>
+    where F: FnMut(Geometry<f64>) -> Result<(), Error>
+{
+    match value {
+        Value::GeometryCollection(geometries) => {
+            for geometry in geometries {
+                each_member(geometry.value, f)?;
+            }
+
+            Ok(())
+        },
+        value => f(value.try_into()?)
+    }
+}
+
+// Name the line an error came from, keeping the message of errors that have one
+fn line_error(number: u64, err: Error) -> Error {
+    let message = match err {
+        Error::GeoJson(message) => message,
+        err => format!("{:?}", err)
+    };
+
+    Error::GeoJson(format!("line {}: {}", number, message))
+}
+
+fn write_tile<W: Write>(writer: &mut W, tile: (i32, i32, u8)) -> Result<(), Error> {
+    writeln!(writer, "{}/{}/{}", tile.2, tile.0, tile.1)?;
+
+    Ok(())
+}
+
+// Sorted runs spilled to disk, removed again when dropped
+struct Runs {
+    paths: Vec<PathBuf>
+}
+
+impl Runs {
+    fn spill(&mut self, tiles: &[(i32, i32, u8)]) -> Result<(), Error> {
+        let path = run_path();
+        self.paths.push(path.clone());
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+        for tile in tiles {
+            write_run_tile(&mut writer, *tile)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    // Merge the spilled runs and the final in memory run, first merging groups
+    // of MERGE_FAN_IN runs into single runs until few enough are left
+    fn merge<W: Write>(&mut self, last: Vec<(i32, i32, u8)>, writer: &mut W) -> Result<u64, Error> {
+        while self.paths.len() > MERGE_FAN_IN {
+            let path = run_path();
+            let mut run = BufWriter::new(File::create(&path)?);
+
+            let group: Vec<PathBuf> = self.paths.drain(..MERGE_FAN_IN).collect();
+            self.paths.push(path);
+
+            let merged = merge_runs(&group, Vec::new(), |tile| write_run_tile(&mut run, tile))
+                .and_then(|()| run.flush().map_err(Error::from));
+
+            for path in group.iter() {
+                let _ = fs::remove_file(path);
+            }
+            merged?;
+        }
+
+        let mut written: u64 = 0;
+
+        merge_runs(&self.paths, last, |tile| {
+            write_tile(writer, tile)?;
+            written += 1;
+
+            Ok(())
+        })?;
+
+        Ok(written)
+    }
+}
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        for path in self.paths.iter() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn run_path() -> PathBuf {
+    env::temp_dir().join(format!(
+        "tilecover-{}-{}.run",
+        process::id(),
+        RUN_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+// k-way merge of sorted runs and a final in memory run, passing each tile once
+fn merge_runs<F>(paths: &[PathBuf], last: Vec<(i32, i32, u8)>, mut f: F) -> Result<(), Error>
+    where F: FnMut((i32, i32, u8)) -> Result<(), Error>
+{
+    let mut readers: Vec<BufReader<File>> = Vec::with_capacity(paths.len());
+    for path in paths.iter() {
+        readers.push(BufReader::new(File::open(path)?));
+    }
+    let mut last = last.into_iter();
+
+    let mut heap: BinaryHeap<RunHead> = BinaryHeap::new();
+    for (i, reader) in readers.iter_mut().enumerate() {
+        if let Some(tile) = read_run_tile(reader)? {
+            heap.push(Reverse((tile, i)));
+        }
+    }
+    if let Some(tile) = last.next() {
+        heap.push(Reverse((tile, readers.len())));
+    }
+
+    let mut previous: Option<(i32, i32, u8)> = None;
+
+    while let Some(Reverse((tile, i))) = heap.pop() {
+        if previous != Some(tile) {
+            f(tile)?;
+            previous = Some(tile);
+        }
+
+        let next = match readers.get_mut(i) {
+            Some(reader) => read_run_tile(reader)?,
+            None => last.next()
+        };
+        if let Some(next) = next {
+            heap.push(Reverse((next, i)));
+        }
+    }
+
+    Ok(())
+}
+
+fn write_run_tile<W: Write>(writer: &mut W, tile: (i32, i32, u8)) -> Result<(), Error> {
+    writer.write_all(&tile.0.to_le_bytes())?;
+    writer.write_all(&tile.1.to_le_bytes())?;
+    writer.write_all(&[tile.2])?;
+
+    Ok(())
+}
+
+fn read_run_tile<R: Read>(reader: &mut R) -> Result<Option<(i32, i32, u8)>, Error> {
+    let mut buf = [0_u8; RUN_TILE_SIZE];
+
+    match reader.read_exact(&mut buf) {
+        Ok(()) => (),
+        Err(ref err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into())
+    }
+
+    let mut x = [0_u8; 4];
+    let mut y = [0_u8; 4];
+    x.copy_from_slice(&buf[0..4]);
+    y.copy_from_slice(&buf[4..8]);
+
+    Ok(Some((i32::from_le_bytes(x), i32::from_le_bytes(y), buf[8])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINES: &str = concat!(
+        "{\"type\":\"Feature\",\"properties\":{},\"geometry\":{\"type\":\"LineString\",\"coordinates\":[[-106.21719360351562,28.592359801923266],[-106.20075225830078,28.593639529281857]]}}\n",
+        "\n",
+        "\u{1e}{\"type\":\"Point\",\"coordinates\":[-106.21,28.59]}\n",
+        "{\"type\":\"Feature\",\"properties\":{},\"geometry\":null}\n",
+        "{\"type\":\"FeatureCollection\",\"features\":[{\"type\":\"Feature\",\"properties\":{},\"geometry\":{\"type\":\"Point\",\"coordinates\":[10.0,10.0]}}]}\n"
+    );
+
+    fn run(dedup: Option<usize>) -> (u64, String) {
+        let mut out: Vec<u8> = Vec::new();
+
+        let written = match dedup {
+            Some(buffer_tiles) => stream_tiles_dedup(LINES.as_bytes(), &mut out, 14, buffer_tiles),
+            None => stream_tiles(LINES.as_bytes(), &mut out, 14)
+        }.unwrap();
+
+        (written, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_stream_tiles() {
+        assert_eq!(run(None), (4, String::from("14/3357/6833\n14/3358/6833\n14/3358/6833\n14/8647/7734\n")));
+    }
+
+    #[test]
+    fn test_stream_tiles_dedup() {
+        let expected = (3, String::from("14/3357/6833\n14/3358/6833\n14/8647/7734\n"));
+
+        assert_eq!(run(Some(1000)), expected);
+        // every feature spills a run of its own
+        assert_eq!(run(Some(1)), expected);
+    }
+
+    #[test]
+    fn test_stream_many_runs() {
+        // a run per feature, enough for more than one merge pass
+        let lines: String = (0..MERGE_FAN_IN * 3).map(|i| {
+            format!("{{\"type\":\"Point\",\"coordinates\":[{},0.0]}}\n", (i % 50) as f64 * 2.0)
+        }).collect();
+
+        let mut merged: Vec<u8> = Vec::new();
+        let mut sorted: Vec<u8> = Vec::new();
+        let written = stream_tiles_dedup(lines.as_bytes(), &mut merged, 10, 1).unwrap();
+        stream_tiles_dedup(lines.as_bytes(), &mut sorted, 10, 1000).unwrap();
+
+        assert_eq!(written, 50);
+        assert_eq!(String::from_utf8(merged).unwrap(), String::from_utf8(sorted).unwrap());
+    }
+
+    #[test]
+    fn test_stream_geometry_collection() {
+        let mut out: Vec<u8> = Vec::new();
+        let line = "{\"type\":\"GeometryCollection\",\"geometries\":[{\"type\":\"Point\",\"coordinates\":[10.0,10.0]},{\"type\":\"GeometryCollection\",\"geometries\":[{\"type\":\"Point\",\"coordinates\":[-106.21,28.59]}]}]}\n";
+
+        assert_eq!(stream_tiles(line.as_bytes(), &mut out, 14).unwrap(), 2);
+        assert_eq!(String::from_utf8(out).unwrap(), "14/8647/7734\n14/3358/6833\n");
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        let mut out: Vec<u8> = Vec::new();
+
+        match stream_tiles("{\"type\":\"Point\",\"coordinates\":[0,0]}\n{\"type\":".as_bytes(), &mut out, 1) {
+            Err(Error::GeoJson(message)) => assert!(message.starts_with("line 2: ")),
+            other => panic!("expected a GeoJSON error, got {:?}", other)
+        }
+
+        let mut out: Vec<u8> = Vec::new();
+        assert_eq!(
+            stream_tiles("\n{\"type\":\"Point\",\"coordinates\":[0,0]}\n".as_bytes(), &mut out, 200),
+            Err(Error::GeoJson(String::from("line 2: ZoomTooLarge(200)")))
+        );
+    }
+
+    #[test]
+    fn test_stream_empty_coordinates() {
+        let mut out: Vec<u8> = Vec::new();
+        let lines = concat!(
+            "{\"type\":\"Feature\",\"properties\":{},\"geometry\":{\"type\":\"LineString\",\"coordinates\":[]}}\n",
+            "{\"type\":\"Polygon\",\"coordinates\":[[]]}\n",
+            "{\"type\":\"Point\",\"coordinates\":[10.0,10.0]}\n"
+        );
+
+        assert_eq!(stream_tiles(lines.as_bytes(), &mut out, 14), Ok(1));
+        assert_eq!(String::from_utf8(out).unwrap(), "14/8647/7734\n");
+    }
+}
@@ -0,0 +1,295 @@
+/*!
+ * Serde helpers for tiles, for use with `#[serde(with = "...")]`
+ *
+ * Tiles serialize as `[x, y, z]` out of the box, these modules provide the
+ * alternative forms
+ */
+
+use std::fmt;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserializer, Serializer};
+
+use super::{format_tile, parse_tile, TileFormat};
+
+/**
+ * A single tile as a `"z/x/y"` string
+ */
+pub mod string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(tile: &(i32, i32, u8), serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_tile(*tile, TileFormat::Slash))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<(i32, i32, u8), D::Error> {
+        deserializer.deserialize_str(TileStringVisitor)
+    }
+}
+
+/**
+ * A list of tiles as `"z/x/y"` strings
+ */
+pub mod strings {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(tiles: &[(i32, i32, u8)], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(tiles.len()))?;
+        for tile in tiles {
+            seq.serialize_element(&format_tile(*tile, TileFormat::Slash))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(i32, i32, u8)>, D::Error> {
+        deserializer.deserialize_seq(TileStringsVisitor)
+    }
+}
+
+/**
+ * A list of tiles packed into bytes
+ *
+ * Each tile is stored as its zoom followed by the zigzag varint deltas of x
+ * and y from the previous tile, so a sorted cover takes around 3 bytes a tile
+ * rather than the 9 or more of `[x, y, z]`
+ */
+pub mod compact {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(tiles: &[(i32, i32, u8)], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&encode(tiles))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(i32, i32, u8)>, D::Error> {
+        deserializer.deserialize_bytes(CompactVisitor)
+    }
+}
+
+struct TileStringVisitor;
+
+impl<'de> Visitor<'de> for TileStringVisitor {
+    type Value = (i32, i32, u8);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a tile as \"z/x/y\"")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        parse_tile(value).map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+}
+
+struct TileStringsVisitor;
+
+impl<'de> Visitor<'de> for TileStringsVisitor {
+    type Value = Vec<(i32, i32, u8)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of tiles as \"z/x/y\"")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut tiles: Vec<(i32, i32, u8)> = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+
+        // owned, as strings can't be borrowed from readers or when they hold escapes
+        while let Some(value) = seq.next_element::<String>()? {
+            match parse_tile(&value) {
+                Ok(tile) => tiles.push(tile),
+                Err(_) => return Err(de::Error::invalid_value(de::Unexpected::Str(&value), &TileStringVisitor))
+            }
+        }
+
+        Ok(tiles)
+    }
+}
+
+struct CompactVisitor;
+
+impl<'de> Visitor<'de> for CompactVisitor {
+    type Value = Vec<(i32, i32, u8)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a compact list of tiles")
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+        decode(value).ok_or_else(|| E::invalid_value(de::Unexpected::Bytes(value), &self))
+    }
+
+    // Self-describing formats like JSON hand bytes back as a sequence
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+
+        self.visit_bytes(&bytes)
+    }
+}
+
+fn encode(tiles: &[(i32, i32, u8)]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(tiles.len() * 3);
+    let mut previous = (0_i64, 0_i64);
+
+    for tile in tiles {
+        bytes.push(tile.2);
+        write_varint(&mut bytes, zigzag(i64::from(tile.0) - previous.0));
+        write_varint(&mut bytes, zigzag(i64::from(tile.1) - previous.1));
+
+        previous = (i64::from(tile.0), i64::from(tile.1));
+    }
+
+    bytes
+}
+
+fn decode(mut bytes: &[u8]) -> Option<Vec<(i32, i32, u8)>> {
+    let mut tiles: Vec<(i32, i32, u8)> = Vec::with_capacity(bytes.len() / 3);
+    let mut previous = (0_i64, 0_i64);
+
+    while let Some((&z, rest)) = bytes.split_first() {
+        bytes = rest;
+
+        let x = previous.0.checked_add(unzigzag(read_varint(&mut bytes)?))?;
+        let y = previous.1.checked_add(unzigzag(read_varint(&mut bytes)?))?;
+
+        tiles.push((i32_from(x)?, i32_from(y)?, z));
+        previous = (x, y);
+    }
+
+    Some(tiles)
+}
+
+fn i32_from(value: i64) -> Option<i32> {
+    if value < i64::from(i32::MIN) || value > i64::from(i32::MAX) {
+        None
+    } else {
+        Some(value as i32)
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value: u64 = 0;
+
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{tiles, Error, TileDiff};
+    use geo::Point;
+
+    extern crate bincode;
+    extern crate serde_json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Job {
+        #[serde(with = "string")]
+        tile: (i32, i32, u8),
+        #[serde(with = "strings")]
+        cover: Vec<(i32, i32, u8)>,
+        #[serde(with = "compact")]
+        packed: Vec<(i32, i32, u8)>
+    }
+
+    #[test]
+    fn test_serde_json() {
+        let job = Job {
+            tile: (5, 10, 4),
+            cover: vec![(0, 0, 0), (1, 2, 3)],
+            packed: vec![(1, 2, 3), (2, 1, 3)]
+        };
+
+        let json = serde_json::to_string(&job).unwrap();
+        assert_eq!(json, r#"{"tile":"4/5/10","cover":["0/0/0","3/1/2"],"packed":[3,2,4,3,2,1]}"#);
+        assert_eq!(serde_json::from_str::<Job>(&json).unwrap(), job);
+
+        assert!(serde_json::from_str::<Job>(r#"{"tile":"4/16/10","cover":[],"packed":[]}"#).is_err());
+        assert!(serde_json::from_str::<Job>(r#"{"tile":"4/5","cover":[],"packed":[]}"#).is_err());
+        assert!(serde_json::from_str::<Job>(r#"{"tile":"4/5/10","cover":[],"packed":[3,128]}"#).is_err());
+
+        assert_eq!(serde_json::to_string(&(1, 2, 3)).unwrap(), "[1,2,3]");
+    }
+
+    #[test]
+    fn test_serde_json_reader() {
+        let json = r#"{"tile":"4\/5\/10","cover":["0/0/0","3\/1\/2"],"packed":[3,2,4]}"#;
+
+        let job: Job = serde_json::from_reader(json.as_bytes()).unwrap();
+        assert_eq!(job, Job {
+            tile: (5, 10, 4),
+            cover: vec![(0, 0, 0), (1, 2, 3)],
+            packed: vec![(1, 2, 3)]
+        });
+        assert_eq!(serde_json::from_str::<Job>(json).unwrap(), job);
+    }
+
+    #[test]
+    fn test_serde_bincode() {
+        let cover = tiles(&Point::new(-77.15664982795715, 38.87419791355846).into(), 20).unwrap();
+
+        let job = Job {
+            tile: cover[0],
+            cover: cover.clone(),
+            packed: tiles(&geo::LineString::from(vec![(-106.21, 28.59), (-106.20, 28.60)]).into(), 18).unwrap()
+        };
+
+        let bytes = bincode::serialize(&job).unwrap();
+        assert_eq!(bincode::deserialize::<Job>(&bytes).unwrap(), job);
+
+        // well under the 9 bytes a tile of plain tuples
+        let packed = bincode::serialize(&(job.packed.len(), job.packed.clone())).unwrap().len();
+        assert!(encode(&job.packed).len() * 2 < packed);
+    }
+
+    #[test]
+    fn test_serde_types() {
+        let err = Error::SridNotSupported(2154);
+        assert_eq!(serde_json::to_string(&err).unwrap(), r#"{"SridNotSupported":2154}"#);
+        assert_eq!(serde_json::from_str::<Error>(r#"{"SridNotSupported":2154}"#).unwrap(), err);
+        assert_eq!(serde_json::from_str::<Error>(r#""GeomTypeNotSupported""#).unwrap(), Error::GeomTypeNotSupported);
+
+        let diff = TileDiff {
+            missing: vec![(1, 1, 1)],
+            extraneous: vec![]
+        };
+        assert_eq!(serde_json::from_str::<TileDiff>(&serde_json::to_string(&diff).unwrap()).unwrap(), diff);
+    }
+
+    #[test]
+    fn test_compact_encoding() {
+        let cover = vec![(0, 0, 0), (2147483647, 2147483647, 31), (0, 1, 1), (-1, -1, 2)];
+        assert_eq!(decode(&encode(&cover)).unwrap(), cover);
+
+        assert_eq!(decode(&[]).unwrap(), vec![]);
+        assert_eq!(decode(&[1, 2]), None);
+    }
+}
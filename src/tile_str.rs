@@ -0,0 +1,174 @@
+use super::{tile_range, TileRange, Error, MAX_ZOOM};
+
+/**
+ * Text formats of a single tile
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TileFormat {
+    /// `z/x/y`, as used in tile URLs
+    Slash,
+    /// `z-x-y`, as used in file names
+    Dash,
+    /// `x,y,z`
+    Comma
+}
+
+/**
+ * Format a tile as text
+ */
+pub fn format_tile(tile: (i32, i32, u8), format: TileFormat) -> String {
+    match format {
+        TileFormat::Slash => format!("{}/{}/{}", tile.2, tile.0, tile.1),
+        TileFormat::Dash => format!("{}-{}-{}", tile.2, tile.0, tile.1),
+        TileFormat::Comma => format!("{},{},{}", tile.0, tile.1, tile.2)
+    }
+}
+
+/**
+ * Parse a tile from `z/x/y`, `z-x-y` or `x,y,z` text
+ *
+ * The zoom must be at most `MAX_ZOOM` and x and y within `0..2^z`
+ */
+pub fn parse_tile(text: &str) -> Result<(i32, i32, u8), Error> {
+    let text = text.trim();
+
+    let (parts, format) = if text.contains('/') {
+        (split3(text, '/'), TileFormat::Slash)
+    } else if text.contains(',') {
+        (split3(text, ','), TileFormat::Comma)
+    } else {
+        (split3(text, '-'), TileFormat::Dash)
+    };
+
+    let parts = parts.ok_or_else(|| parse_error(text, "expected 3 parts"))?;
+
+    let (z, x, y) = match format {
+        TileFormat::Comma => (parts[2], parts[0], parts[1]),
+        _ => (parts[0], parts[1], parts[2])
+    };
+
+    let z = parse_zoom(text, z)?;
+    let x = parse_coord(text, x, z)?;
+    let y = parse_coord(text, y, z)?;
+
+    Ok((x, y, z))
+}
+
+/**
+ * Parse a range of tiles like `14/100-120/200-210` into an iterator over each tile it holds
+ *
+ * x and y each take either a single value or an inclusive `start-end` range.
+ * Tiles are yielded sorted by x, then y, like `tiles`. Ranges are iterated
+ * lazily, as a high zoom range can hold more tiles than fit in memory
+ */
+pub fn parse_tile_range(text: &str) -> Result<TileRange, Error> {
+    let text = text.trim();
+
+    let parts = split3(text, '/').ok_or_else(|| parse_error(text, "expected z/x/y"))?;
+
+    let z = parse_zoom(text, parts[0])?;
+    let (min_x, max_x) = parse_span(text, parts[1], z)?;
+    let (min_y, max_y) = parse_span(text, parts[2], z)?;
+
+    Ok(tile_range(min_x, min_y, max_x, max_y, z))
+}
+
+fn split3(text: &str, separator: char) -> Option<[&str; 3]> {
+    let mut parts = text.split(separator);
+
+    let split = [parts.next()?, parts.next()?, parts.next()?];
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(split)
+}
+
+fn parse_zoom(text: &str, z: &str) -> Result<u8, Error> {
+    match z.trim().parse::<u8>() {
+        Ok(z) if z <= MAX_ZOOM => Ok(z),
+        _ => Err(parse_error(text, "invalid zoom"))
+    }
+}
+
+fn parse_coord(text: &str, value: &str, z: u8) -> Result<i32, Error> {
+    match value.trim().parse::<i32>() {
+        Ok(value) if value >= 0 && i64::from(value) < 1_i64 << z => Ok(value),
+        Ok(_) => Err(parse_error(text, "x and y must be within 0..2^z")),
+        Err(_) => Err(parse_error(text, "invalid x or y"))
+    }
+}
+
+fn parse_span(text: &str, span: &str, z: u8) -> Result<(i32, i32), Error> {
+    let (start, end) = match span.find('-') {
+        Some(dash) => (parse_coord(text, &span[..dash], z)?, parse_coord(text, &span[dash + 1..], z)?),
+        None => {
+            let value = parse_coord(text, span, z)?;
+            (value, value)
+        }
+    };
+
+    if start > end {
+        return Err(parse_error(text, "range start is after its end"));
+    }
+
+    Ok((start, end))
+}
+
+fn parse_error(text: &str, reason: &str) -> Error {
+    Error::TileParse(format!("'{}': {}", text, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_tile() {
+        assert_eq!(format_tile((4579, 6271, 14), TileFormat::Slash), "14/4579/6271");
+        assert_eq!(format_tile((4579, 6271, 14), TileFormat::Dash), "14-4579-6271");
+        assert_eq!(format_tile((4579, 6271, 14), TileFormat::Comma), "4579,6271,14");
+    }
+
+    #[test]
+    fn test_parse_tile() {
+        assert_eq!(parse_tile("14/4579/6271"), Ok((4579, 6271, 14)));
+        assert_eq!(parse_tile(" 14-4579-6271\n"), Ok((4579, 6271, 14)));
+        assert_eq!(parse_tile("4579, 6271, 14"), Ok((4579, 6271, 14)));
+        assert_eq!(parse_tile("0/0/0"), Ok((0, 0, 0)));
+
+        for format in &[TileFormat::Slash, TileFormat::Dash, TileFormat::Comma] {
+            assert_eq!(parse_tile(&format_tile((1, 2, 3), *format)), Ok((1, 2, 3)));
+        }
+
+        assert_eq!(parse_tile("14/16384/0"), Err(Error::TileParse(String::from("'14/16384/0': x and y must be within 0..2^z"))));
+        assert_eq!(parse_tile("1-1--1"), Err(Error::TileParse(String::from("'1-1--1': expected 3 parts"))));
+        assert_eq!(parse_tile("40/0/0"), Err(Error::TileParse(String::from("'40/0/0': invalid zoom"))));
+        assert_eq!(parse_tile("1/a/0"), Err(Error::TileParse(String::from("'1/a/0': invalid x or y"))));
+        assert_eq!(parse_tile("1/0"), Err(Error::TileParse(String::from("'1/0': expected 3 parts"))));
+    }
+
+    #[test]
+    fn test_parse_tile_range() {
+        assert_eq!(parse_tile_range("14/100-101/200-202").map(Iterator::collect), Ok(vec![
+            (100, 200, 14),
+            (100, 201, 14),
+            (100, 202, 14),
+            (101, 200, 14),
+            (101, 201, 14),
+            (101, 202, 14)
+        ]));
+        assert_eq!(parse_tile_range("2/3/0-3").unwrap().count(), 4);
+        assert_eq!(parse_tile_range("14/100-120/200-210").unwrap().count(), 21 * 11);
+
+        let mut all = parse_tile_range("30/0-1073741823/0-1073741823").unwrap();
+        assert_eq!(all.range(), (0, 0, 1073741823, 1073741823));
+        assert_eq!(all.next(), Some((0, 0, 30)));
+        assert_eq!(all.next(), Some((0, 1, 30)));
+
+        assert_eq!(parse_tile_range("14/120-100/200").err(), Some(Error::TileParse(String::from("'14/120-100/200': range start is after its end"))));
+        assert_eq!(parse_tile_range("2/0-4/0").err(), Some(Error::TileParse(String::from("'2/0-4/0': x and y must be within 0..2^z"))));
+    }
+}
@@ -0,0 +1,84 @@
+use super::{tile_to_quadkey, MAX_ZOOM};
+
+/**
+ * Expand a tile URL template for a single tile
+ *
+ * Supported placeholders are `{z}`, `{x}`, `{y}`, `{-y}` (TMS row, counted
+ * from the south), `{quadkey}` and `{s}`. `{s}` rotates through `subdomains`
+ * based on the tile's position, so a tile always maps to the same host.
+ * Unknown placeholders are left as is. Panics when the zoom is above `MAX_ZOOM`
+ */
+pub fn tile_url(template: &str, tile: (i32, i32, u8), subdomains: &[&str]) -> String {
+    assert!(tile.2 <= MAX_ZOOM, "zoom {} is above MAX_ZOOM", tile.2);
+
+    let mut url = String::with_capacity(template.len() + 16);
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        url.push_str(&rest[..start]);
+
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        };
+
+        match &rest[start + 1..end] {
+            "z" => url.push_str(&tile.2.to_string()),
+            "x" => url.push_str(&tile.0.to_string()),
+            "y" => url.push_str(&tile.1.to_string()),
+            "-y" => url.push_str(&((1_i64 << tile.2) - 1 - tile.1 as i64).to_string()),
+            "quadkey" => url.push_str(&tile_to_quadkey(tile)),
+            "s" if !subdomains.is_empty() => {
+                let index = (tile.0 as i64 + tile.1 as i64).unsigned_abs() as usize % subdomains.len();
+                url.push_str(subdomains[index]);
+            },
+            _ => url.push_str(&rest[start..=end])
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    url.push_str(rest);
+
+    url
+}
+
+/**
+ * Expand a tile URL template for each tile of a cover
+ */
+pub fn tile_urls(template: &str, tiles: &[(i32, i32, u8)], subdomains: &[&str]) -> Vec<String> {
+    tiles.iter().map(|tile| tile_url(template, *tile, subdomains)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_url() {
+        assert_eq!(tile_url("https://host/{z}/{x}/{y}.pbf", (4579, 6271, 14), &[]), "https://host/14/4579/6271.pbf");
+        assert_eq!(tile_url("https://host/{z}/{x}/{-y}.png", (3, 5, 3), &[]), "https://host/3/3/2.png");
+        assert_eq!(tile_url("https://host/tiles/{quadkey}.jpeg", (3, 5, 3), &[]), "https://host/tiles/213.jpeg");
+        assert_eq!(tile_url("https://{s}.host/{z}/{x}/{y}", (3, 5, 3), &["a", "b", "c"]), "https://c.host/3/3/5");
+        assert_eq!(tile_url("https://{s}.host/{z}/{x}/{y}", (3, 5, 3), &[]), "https://{s}.host/3/3/5");
+        assert_eq!(tile_url("https://host/{z}/{x}/{y}?key={key}&v={", (0, 0, 0), &[]), "https://host/0/0/0?key={key}&v={");
+    }
+
+    #[test]
+    fn test_tile_urls() {
+        assert_eq!(tile_urls("{s}/{z}/{x}/{y}", &[(0, 0, 1), (1, 0, 1), (1, 1, 1)], &["a", "b"]), vec![
+            "a/1/0/0",
+            "b/1/1/0",
+            "a/1/1/1"
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "zoom 40 is above MAX_ZOOM")]
+    fn test_tile_url_zoom_limit() {
+        tile_url("{quadkey}", (0, 0, 40), &[]);
+    }
+}